@@ -2,8 +2,8 @@ use std::fs;
 use std::time::Duration;
 
 use anyhow::Context;
-use clap::{Parser, Subcommand};
-use goku_core::settings::{Auth, Header, Settings};
+use clap::{Args, Parser, Subcommand};
+use goku_core::settings::{Auth, Executor, Header, MultipartField, Payload, Settings};
 
 // ---------------------------------------------------------------------------
 // Top-level CLI structure (supports subcommands)
@@ -17,7 +17,6 @@ pub struct Cli {
     pub command: Option<Command>,
 
     // ── inline benchmark flags (no subcommand = run benchmark directly) ──
-
     /// Runs in verbose mode
     #[arg(short, long, global = false)]
     pub verbose: bool,
@@ -27,10 +26,20 @@ pub struct Cli {
     #[arg(short, long, conflicts_with = "scenario")]
     pub target: Option<String>,
 
-    /// Path to a file whose contents will be used as the request body
+    /// Path to a file whose contents will be used as the request body (sent as raw bytes
+    /// with Content-Type application/octet-stream when it is not UTF-8 text)
     #[arg(short, long, conflicts_with = "scenario")]
     pub request_body: Option<String>,
 
+    /// Form field in "key=value" format, sent application/x-www-form-urlencoded (repeatable)
+    #[arg(long, conflicts_with_all = ["scenario", "request_body", "multipart"])]
+    pub form: Option<Vec<String>>,
+
+    /// Multipart field in "name=value" or "name=@path" (file) format, sent
+    /// multipart/form-data (repeatable)
+    #[arg(long, conflicts_with_all = ["scenario", "request_body"])]
+    pub multipart: Option<Vec<String>>,
+
     /// Number of concurrent workers
     #[arg(short, long, default_value_t = 1, conflicts_with = "scenario")]
     pub clients: u32,
@@ -75,6 +84,25 @@ pub struct Cli {
     #[arg(long)]
     pub rps: Option<u32>,
 
+    /// Load model: closed (default) | constant-arrival-rate.
+    /// With constant-arrival-rate, requests start at --rps regardless of response times
+    /// and --clients is the maximum number of requests in flight
+    #[arg(long, default_value = "closed", conflicts_with = "scenario")]
+    pub executor: String,
+
+    /// Read each response body to the end (discarding it); latency then includes the download
+    /// and received bytes/throughput are reported
+    #[arg(long, default_value_t = false, conflicts_with = "scenario")]
+    pub read_body: bool,
+
+    /// Keep a cookie jar per worker, so session cookies are sent back on later requests
+    #[arg(long, default_value_t = false, conflicts_with = "scenario")]
+    pub cookies: bool,
+
+    /// Clear each worker's cookies and extracted variables at the start of every iteration
+    #[arg(long, default_value_t = false, conflicts_with = "scenario")]
+    pub reset_session: bool,
+
     /// Bearer token for Authorization header (e.g. --auth-bearer mytoken)
     #[arg(long, conflicts_with_all = ["auth_basic", "scenario"])]
     pub auth_bearer: Option<String>,
@@ -87,7 +115,7 @@ pub struct Cli {
     #[arg(long)]
     pub output_file: Option<String>,
 
-    /// Write per-request log (timestamp_ms,status,latency_ms) to this CSV file
+    /// Write per-request log (timestamp, status, latency and phase timings) to this CSV file
     #[arg(long)]
     pub results_log: Option<String>,
 
@@ -102,6 +130,11 @@ pub struct Cli {
     /// Disable HTTP keep-alive / connection reuse
     #[arg(long, default_value_t = false)]
     pub disable_keepalive: bool,
+
+    /// Seed for random template values, making generated URLs, headers and bodies
+    /// reproducible (overrides the scenario's `seed`)
+    #[arg(long)]
+    pub seed: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -117,6 +150,60 @@ pub enum Command {
         /// Second result file (candidate)
         candidate: String,
     },
+    /// Find the highest request rate the target sustains within latency and error thresholds.
+    /// Load options (target, clients, scenario, …) are given before the subcommand.
+    Capacity(CapacityArgs),
+}
+
+/// Search bounds and thresholds of the `capacity` subcommand.
+#[derive(Args, Debug, Clone)]
+pub struct CapacityArgs {
+    /// Highest acceptable p99 latency in milliseconds (corrected for coordinated omission)
+    #[arg(long)]
+    pub max_p99_ms: Option<u64>,
+
+    /// Highest acceptable share of failed requests, in percent. Errors, failed checks and
+    /// dropped iterations count as failures
+    #[arg(long, default_value_t = 1.0)]
+    pub max_error_rate: f64,
+
+    /// Rate of the first probe, in requests per second
+    #[arg(long, default_value_t = 50)]
+    pub start_rps: u32,
+
+    /// Rate increase between probes until one fails
+    #[arg(long, default_value_t = 50)]
+    pub step_rps: u32,
+
+    /// Highest rate to try
+    #[arg(long, default_value_t = 10_000)]
+    pub max_rps: u32,
+
+    /// Stop narrowing once the breaking point is known within this many requests per second
+    #[arg(long, default_value_t = 10)]
+    pub resolution: u32,
+
+    /// Length of each probe in seconds
+    #[arg(long, default_value_t = 10)]
+    pub probe_duration: u64,
+}
+
+impl CapacityArgs {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.start_rps == 0 || self.step_rps == 0 || self.resolution == 0 {
+            anyhow::bail!("--start-rps, --step-rps and --resolution must be greater than 0");
+        }
+        if self.max_rps < self.start_rps {
+            anyhow::bail!("--max-rps must be at least --start-rps");
+        }
+        if self.probe_duration == 0 {
+            anyhow::bail!("--probe-duration must be greater than 0");
+        }
+        if !(0.0..=100.0).contains(&self.max_error_rate) {
+            anyhow::bail!("--max-error-rate must be between 0 and 100");
+        }
+        Ok(())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -124,7 +211,7 @@ pub enum Command {
 // ---------------------------------------------------------------------------
 
 impl Cli {
-    pub fn to_settings(self) -> anyhow::Result<Settings> {
+    pub fn into_settings(self) -> anyhow::Result<Settings> {
         let output_format = self.output.parse().unwrap_or_default();
         let auth = parse_auth(self.auth_bearer.as_deref(), self.auth_basic.as_deref())?;
 
@@ -137,18 +224,25 @@ impl Cli {
         settings.output_file = self.output_file;
         settings.results_log = self.results_log;
         settings.live_stats = self.live_stats;
+        if self.seed.is_some() {
+            settings.seed = self.seed;
+        }
         Ok(settings)
     }
 
     fn from_args(args: &Cli, auth: Option<Auth>) -> anyhow::Result<Settings> {
         let headers = parse_headers(args.headers.as_deref())?;
+        let executor: Executor = args.executor.parse()?;
 
-        let body = match &args.request_body {
-            None => None,
+        let (body, body_file) = match &args.request_body {
+            None => (None, None),
             Some(file) => {
-                let content = fs::read_to_string(file)
+                let content = fs::read(file)
                     .with_context(|| format!("Failed to read body file '{}'", file))?;
-                Some(content)
+                match String::from_utf8(content) {
+                    Ok(text) => (Some(Payload::Text(text)), None),
+                    Err(_) => (None, Some(file.clone())),
+                }
             }
         };
 
@@ -160,6 +254,9 @@ impl Cli {
             })?,
             keep_alive: None,
             body,
+            body_file,
+            form: parse_form(args.form.as_deref())?,
+            multipart: parse_multipart(args.multipart.as_deref())?,
             headers,
             duration: args.duration,
             verbose: args.verbose,
@@ -176,6 +273,18 @@ impl Cli {
             live_stats: None,
             pool_idle_timeout: args.pool_idle_timeout,
             disable_keepalive: args.disable_keepalive,
+            executor,
+            read_body: args.read_body,
+            checks: vec![],
+            cookies: args.cookies,
+            reset_session: args.reset_session,
+            stages: vec![],
+            think_time: None,
+            flows: vec![],
+            setup: vec![],
+            teardown: vec![],
+            feeders: vec![],
+            seed: None,
         })
     }
 }
@@ -191,16 +300,8 @@ fn parse_headers(raw: Option<&[String]>) -> anyhow::Result<Option<Vec<Header>>>
     let mut headers = Vec::with_capacity(headers_raw.len());
     for h in headers_raw {
         let mut split = h.splitn(2, ':');
-        let key = split
-            .next()
-            .unwrap_or("")
-            .trim()
-            .to_string();
-        let value = split
-            .next()
-            .unwrap_or("")
-            .trim()
-            .to_string();
+        let key = split.next().unwrap_or("").trim().to_string();
+        let value = split.next().unwrap_or("").trim().to_string();
         if key.is_empty() {
             anyhow::bail!("Invalid header '{}': header name cannot be empty", h);
         }
@@ -209,6 +310,52 @@ fn parse_headers(raw: Option<&[String]>) -> anyhow::Result<Option<Vec<Header>>>
     Ok(Some(headers))
 }
 
+/// `key=value` pairs, in order; a repeated key is sent once per value.
+fn parse_form(raw: Option<&[String]>) -> anyhow::Result<Option<Vec<(String, String)>>> {
+    let Some(fields) = raw else {
+        return Ok(None);
+    };
+    let mut form = Vec::with_capacity(fields.len());
+    for field in fields {
+        let Some((key, value)) = field.split_once('=') else {
+            anyhow::bail!("Invalid form field '{}': expected 'key=value'", field);
+        };
+        form.push((key.to_string(), value.to_string()));
+    }
+    Ok(Some(form))
+}
+
+/// `name=value` text parts and `name=@path` file parts.
+fn parse_multipart(raw: Option<&[String]>) -> anyhow::Result<Option<Vec<MultipartField>>> {
+    let Some(fields) = raw else {
+        return Ok(None);
+    };
+    let mut parts = Vec::with_capacity(fields.len());
+    for field in fields {
+        let Some((name, value)) = field.split_once('=') else {
+            anyhow::bail!(
+                "Invalid multipart field '{}': expected 'name=value' or 'name=@path'",
+                field
+            );
+        };
+        if name.is_empty() {
+            anyhow::bail!("Invalid multipart field '{}': name cannot be empty", field);
+        }
+        let (value, file) = match value.strip_prefix('@') {
+            Some(path) => (None, Some(path.to_string())),
+            None => (Some(value.to_string()), None),
+        };
+        parts.push(MultipartField {
+            name: name.to_string(),
+            value,
+            file,
+            filename: None,
+            content_type: None,
+        });
+    }
+    Ok(Some(parts))
+}
+
 fn parse_auth(bearer: Option<&str>, basic: Option<&str>) -> anyhow::Result<Option<Auth>> {
     match (bearer, basic) {
         (Some(token), None) => Ok(Some(Auth::Bearer {
@@ -263,6 +410,31 @@ mod tests {
         assert!(parse_headers(None).unwrap().is_none());
     }
 
+    #[test]
+    fn parse_form_keeps_order_repeats_and_splits_on_first_equals() {
+        let raw = vec![
+            "q=a=b".to_string(),
+            "tag=x".to_string(),
+            "tag=y".to_string(),
+        ];
+        let form = parse_form(Some(&raw)).unwrap().unwrap();
+        let keys = form.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["q", "tag", "tag"]);
+        assert_eq!(form[0].1, "a=b");
+        assert_eq!(form[2].1, "y");
+        assert!(parse_form(Some(&["nope".to_string()])).is_err());
+    }
+
+    #[test]
+    fn parse_multipart_reads_files_after_at() {
+        let raw = vec!["title=hi".to_string(), "photo=@./photo.png".to_string()];
+        let parts = parse_multipart(Some(&raw)).unwrap().unwrap();
+        assert_eq!(parts[0].value.as_deref(), Some("hi"));
+        assert_eq!(parts[1].file.as_deref(), Some("./photo.png"));
+        assert!(parts[1].value.is_none());
+        assert!(parse_multipart(Some(&["=x".to_string()])).is_err());
+    }
+
     #[test]
     fn parse_auth_bearer() {
         let auth = parse_auth(Some("mytoken"), None).unwrap().unwrap();
@@ -284,4 +456,4 @@ mod tests {
     fn parse_auth_both_errors() {
         assert!(parse_auth(Some("tok"), Some("u:p")).is_err());
     }
-}
\ No newline at end of file
+}
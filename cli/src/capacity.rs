@@ -0,0 +1,279 @@
+//! `goku capacity`: searches for the highest arrival rate that stays within the thresholds.
+//!
+//! Each probe is an ordinary open-model run (`constant-arrival-rate` for `--probe-duration`
+//! seconds) executed with [`run`] and aggregated into a [`Report`]. The rate grows by
+//! `--step-rps` until a probe fails, then the interval between the best passing and the first
+//! failing rate is bisected down to `--resolution`.
+
+use anyhow::Result;
+use tokio::sync::{mpsc, watch};
+
+use crate::args::CapacityArgs;
+use goku_core::benchmark::{BenchmarkResult, Report};
+use goku_core::execution::run;
+use goku_core::settings::{Executor, Settings};
+
+/// Outcome of one probe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Probe {
+    /// Offered arrival rate.
+    pub target_rps: u32,
+    /// Completed requests per second.
+    pub achieved_rps: f64,
+    pub requests: u64,
+    /// p99 latency from the intended send time, in microseconds.
+    pub p99_us: u64,
+    /// Failed requests (errors, failed checks, dropped iterations) as a share of all iterations.
+    pub error_rate: f64,
+    pub passed: bool,
+}
+
+/// Result of the whole search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityReport {
+    pub probes: Vec<Probe>,
+    /// Highest rate whose probe passed.
+    pub max_sustainable_rps: Option<u32>,
+    /// Lowest rate whose probe failed, if one did.
+    pub breaking_rps: Option<u32>,
+    pub max_p99_ms: Option<u64>,
+    pub max_error_rate: f64,
+    /// The search was stopped by Ctrl-C before converging.
+    pub interrupted: bool,
+}
+
+/// Rate selection: linear steps up to the first failure, then bisection.
+#[derive(Debug)]
+struct Search {
+    start: u32,
+    step: u32,
+    max: u32,
+    resolution: u32,
+    last: Option<u32>,
+    best: Option<u32>,
+    breaking: Option<u32>,
+}
+
+impl Search {
+    fn new(args: &CapacityArgs) -> Self {
+        Search {
+            start: args.start_rps,
+            step: args.step_rps,
+            max: args.max_rps,
+            resolution: args.resolution,
+            last: None,
+            best: None,
+            breaking: None,
+        }
+    }
+
+    /// Rate of the next probe, or `None` once the search is over.
+    fn next_rate(&self) -> Option<u32> {
+        match (self.last, self.breaking) {
+            (None, _) => Some(self.start),
+            (Some(last), None) if last >= self.max => None,
+            (Some(last), None) => Some(last.saturating_add(self.step).min(self.max)),
+            (Some(_), Some(breaking)) => {
+                let best = self.best.unwrap_or(0);
+                (breaking - best > self.resolution).then(|| best + (breaking - best) / 2)
+            }
+        }
+    }
+
+    fn record(&mut self, rate: u32, passed: bool) {
+        self.last = Some(rate);
+        if passed {
+            self.best = self.best.max(Some(rate));
+        } else {
+            self.breaking = Some(self.breaking.map_or(rate, |b| b.min(rate)));
+        }
+    }
+}
+
+/// Runs the search against the load described by `settings`.
+pub async fn search(settings: &Settings, args: &CapacityArgs) -> Result<CapacityReport> {
+    args.validate()?;
+    if !settings.setup.is_empty() || !settings.teardown.is_empty() {
+        anyhow::bail!(
+            "`setup` and `teardown` are not supported by `capacity`: each probe would rerun them"
+        );
+    }
+    let (tx_sigint, rx_sigint) = watch::channel(None);
+    ctrlc::set_handler(move || {
+        tx_sigint.send(Some(())).unwrap_or(());
+    })?;
+
+    let mut search = Search::new(args);
+    let mut probes = vec![];
+    let mut interrupted = false;
+    while let Some(rate) = search.next_rate() {
+        let probe_settings = probe_settings(settings, args, rate);
+        probe_settings.validate()?;
+        let report = probe(probe_settings, rx_sigint.clone()).await?;
+        if rx_sigint.borrow().is_some() {
+            // A cut-short probe says nothing about the rate.
+            interrupted = true;
+            break;
+        }
+        let probe = evaluate(&report, rate, args);
+        eprintln!(
+            "  [capacity] {} rps: {:.1} req/s, p99 {}, errors {:.2}% — {}",
+            rate,
+            probe.achieved_rps,
+            goku_core::benchmark::format_latency(probe.p99_us as f64),
+            probe.error_rate * 100.0,
+            if probe.passed { "pass" } else { "fail" }
+        );
+        search.record(rate, probe.passed);
+        probes.push(probe);
+    }
+
+    Ok(CapacityReport {
+        probes,
+        max_sustainable_rps: search.best,
+        breaking_rps: search.breaking,
+        max_p99_ms: args.max_p99_ms,
+        max_error_rate: args.max_error_rate,
+        interrupted,
+    })
+}
+
+/// The user's load settings turned into a fixed-rate run of `--probe-duration` seconds.
+fn probe_settings(settings: &Settings, args: &CapacityArgs, rate: u32) -> Settings {
+    Settings {
+        executor: Executor::ConstantArrivalRate,
+        rps: Some(rate),
+        duration: Some(args.probe_duration),
+        ramp_up: None,
+        stages: vec![],
+        verbose: false,
+        live_stats: None,
+        ..settings.clone()
+    }
+}
+
+async fn probe(settings: Settings, rx_sigint: watch::Receiver<Option<()>>) -> Result<Report> {
+    let channel_capacity = (settings.clients as usize * 2).min(4096);
+    let (tx, mut rx) = mpsc::channel::<BenchmarkResult>(channel_capacity);
+    run(settings.clone(), tx, Some(rx_sigint)).await?;
+
+    let mut report = Report::new(settings.clients).with_corrected_histogram();
+    while let Some(result) = rx.recv().await {
+        report.add_result(result);
+    }
+    Ok(report)
+}
+
+fn evaluate(report: &Report, rate: u32, args: &CapacityArgs) -> Probe {
+    let failed = report.failed_requests + report.dropped;
+    let iterations = report.hist.len() + report.dropped;
+    let error_rate = if iterations > 0 {
+        failed as f64 / iterations as f64
+    } else {
+        1.0
+    };
+    let p99_us = report
+        .corrected_hist
+        .as_ref()
+        .unwrap_or(&report.hist)
+        .value_at_quantile(0.99);
+    let latency_ok = args
+        .max_p99_ms
+        .is_none_or(|max| p99_us <= max.saturating_mul(1_000));
+    Probe {
+        target_rps: rate,
+        achieved_rps: report.requests_per_second(),
+        requests: report.hist.len(),
+        p99_us,
+        error_rate,
+        passed: iterations > 0 && latency_ok && error_rate * 100.0 <= args.max_error_rate,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goku_core::benchmark::Outcome;
+
+    fn args() -> CapacityArgs {
+        CapacityArgs {
+            max_p99_ms: Some(100),
+            max_error_rate: 1.0,
+            start_rps: 100,
+            step_rps: 100,
+            max_rps: 1_000,
+            resolution: 10,
+            probe_duration: 1,
+        }
+    }
+
+    /// Runs the search against a target that breaks above `limit` rps.
+    fn probed_rates(args: &CapacityArgs, limit: u32) -> (Vec<u32>, Option<u32>) {
+        let mut search = Search::new(args);
+        let mut rates = vec![];
+        while let Some(rate) = search.next_rate() {
+            rates.push(rate);
+            search.record(rate, rate <= limit);
+        }
+        (rates, search.best)
+    }
+
+    #[test]
+    fn steps_up_then_bisects_to_the_resolution() {
+        let (rates, best) = probed_rates(&args(), 342);
+        assert_eq!(rates, vec![100, 200, 300, 400, 350, 325, 337, 343]);
+        assert_eq!(best, Some(337));
+    }
+
+    #[test]
+    fn stops_at_max_rps_when_nothing_fails() {
+        let (rates, best) = probed_rates(
+            &CapacityArgs {
+                max_rps: 250,
+                ..args()
+            },
+            u32::MAX,
+        );
+        assert_eq!(rates, vec![100, 200, 250]);
+        assert_eq!(best, Some(250));
+    }
+
+    #[test]
+    fn bisects_below_a_failing_first_probe() {
+        let (rates, best) = probed_rates(&args(), 30);
+        assert_eq!(rates, vec![100, 50, 25, 37, 31]);
+        assert_eq!(best, Some(25));
+        let (_, best) = probed_rates(&args(), 0);
+        assert_eq!(best, None);
+    }
+
+    #[test]
+    fn probe_fails_on_errors_or_latency() {
+        let result = |code, duration_us| BenchmarkResult {
+            outcome: Outcome::Status(code),
+            duration_us,
+            ..BenchmarkResult::default()
+        };
+        let report_of = |results: Vec<BenchmarkResult>| {
+            let mut r = Report::new(1).with_corrected_histogram();
+            results.into_iter().for_each(|x| r.add_result(x));
+            r
+        };
+
+        let healthy = report_of(vec![result(200, 5_000); 100]);
+        assert!(evaluate(&healthy, 100, &args()).passed);
+
+        let mut with_errors = vec![result(200, 5_000); 98];
+        with_errors.extend(vec![result(503, 5_000); 2]);
+        let probe = evaluate(&report_of(with_errors), 100, &args());
+        assert!((probe.error_rate - 0.02).abs() < 1e-9);
+        assert!(!probe.passed);
+
+        let slow = report_of(vec![result(200, 150_000); 100]);
+        assert!(!evaluate(&slow, 100, &args()).passed);
+    }
+}
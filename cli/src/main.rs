@@ -1,7 +1,12 @@
+// Needed by `serde_json::json!` for the large JSON report objects.
+#![recursion_limit = "256"]
+
 mod args;
+mod capacity;
 mod output;
 
 use std::fmt::{Display, Formatter};
+use std::io::BufWriter;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -11,14 +16,15 @@ use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
 use tokio::sync::{mpsc, watch};
 
-use crate::args::{Cli, Command};
+use crate::args::{CapacityArgs, Cli, Command};
+use crate::capacity::CapacityReport;
 use crate::output::{
-    print_comparison, print_csv, print_json, print_text, print_text_colored, write_results_log,
-    RunSnapshot,
+    print_capacity_csv, print_capacity_json, print_capacity_text, print_comparison, print_csv,
+    print_json, print_text, print_text_colored, ResultsLog, RunSnapshot,
 };
-use goku_core::benchmark::{BenchmarkResult, Report};
+use goku_core::benchmark::{format_latency, BenchmarkResult, Report};
 use goku_core::execution::run;
-use goku_core::settings::{OutputFormat, Settings};
+use goku_core::settings::{Executor, OutputFormat, Settings};
 
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
@@ -29,14 +35,14 @@ static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     // Handle subcommands first
-    if let Some(cmd) = &cli.command {
-        return handle_subcommand(cmd);
+    if let Some(cmd) = cli.command.take() {
+        return handle_subcommand(cmd, cli).await;
     }
 
-    let settings: Settings = cli.to_settings()?;
+    let settings: Settings = cli.into_settings()?;
     settings.validate()?;
 
     run_benchmark(settings).await
@@ -46,15 +52,15 @@ async fn main() -> Result<()> {
 // Subcommand dispatch
 // ---------------------------------------------------------------------------
 
-fn handle_subcommand(cmd: &Command) -> Result<()> {
+async fn handle_subcommand(cmd: Command, cli: Cli) -> Result<()> {
     match cmd {
         Command::Compare {
             baseline,
             candidate,
         } => {
-            let base_raw = std::fs::read_to_string(baseline)
+            let base_raw = std::fs::read_to_string(&baseline)
                 .map_err(|e| anyhow::anyhow!("Cannot read baseline file '{}': {}", baseline, e))?;
-            let cand_raw = std::fs::read_to_string(candidate).map_err(|e| {
+            let cand_raw = std::fs::read_to_string(&candidate).map_err(|e| {
                 anyhow::anyhow!("Cannot read candidate file '{}': {}", candidate, e)
             })?;
 
@@ -67,6 +73,46 @@ fn handle_subcommand(cmd: &Command) -> Result<()> {
 
             print_comparison(&base, &cand);
         }
+        Command::Capacity(args) => {
+            let settings = cli.into_settings()?;
+            run_capacity(settings, args).await?;
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Capacity search
+// ---------------------------------------------------------------------------
+
+async fn run_capacity(settings: Settings, args: CapacityArgs) -> Result<()> {
+    let target_display = describe_target(&settings);
+    println!(
+        "{}",
+        format!(
+            "capacity search on {} with up to {} clients, {}s per probe",
+            target_display, settings.clients, args.probe_duration
+        )
+        .cyan()
+        .bold()
+    );
+
+    let report = capacity::search(&settings, &args).await?;
+    write_capacity_output(&settings, &report)
+}
+
+fn write_capacity_output(settings: &Settings, report: &CapacityReport) -> Result<()> {
+    let mut out: Box<dyn std::io::Write> = match &settings.output_file {
+        Some(path) => Box::new(
+            std::fs::File::create(path)
+                .map_err(|e| anyhow::anyhow!("Cannot create output file '{}': {}", path, e))?,
+        ),
+        None => Box::new(std::io::stdout()),
+    };
+    match &settings.output {
+        OutputFormat::Text => print_capacity_text(report, &mut out),
+        OutputFormat::Json => print_capacity_json(report, &mut out),
+        OutputFormat::Csv => print_capacity_csv(report, &mut out),
     }
     Ok(())
 }
@@ -83,7 +129,7 @@ async fn run_benchmark(settings: Settings) -> Result<()> {
 
     // ── Channels ──────────────────────────────────────────────────────────
     let (tx_sigint, rx_sigint) = watch::channel(None);
-    let channel_capacity = (settings.clients as usize * 2).min(4096);
+    let channel_capacity = (settings.peak_clients() as usize * 2).min(4096);
     let (benchmark_tx, mut benchmark_rx) = mpsc::channel::<BenchmarkResult>(channel_capacity);
 
     ctrlc::set_handler(move || {
@@ -92,7 +138,7 @@ async fn run_benchmark(settings: Settings) -> Result<()> {
 
     // ── Live stats ─────────────────────────────────────────────────────────
     let live_report: Option<Arc<Mutex<Report>>> = settings.live_stats.map(|interval_secs| {
-        let shared = Arc::new(Mutex::new(Report::new(settings.clients)));
+        let shared = Arc::new(Mutex::new(Report::new(settings.peak_clients())));
         let arc_clone = Arc::clone(&shared);
         let secs = interval_secs;
         tokio::spawn(async move {
@@ -102,11 +148,11 @@ async fn run_benchmark(settings: Settings) -> Result<()> {
                     let total = r.hist.len();
                     if total > 0 {
                         eprintln!(
-                            "  [live] requests={} rps={:.1} p50={}ms p95={}ms",
+                            "  [live] requests={} rps={:.1} p50={} p95={}",
                             total,
                             r.requests_per_second(),
-                            r.hist.value_at_quantile(0.50),
-                            r.hist.value_at_quantile(0.95),
+                            format_latency(r.hist.value_at_quantile(0.50) as f64),
+                            format_latency(r.hist.value_at_quantile(0.95) as f64),
                         );
                     }
                 }
@@ -115,20 +161,45 @@ async fn run_benchmark(settings: Settings) -> Result<()> {
         shared
     });
 
+    // ── Results log ────────────────────────────────────────────────────────
+    // Rows are written as results arrive, so a long run never holds them in memory.
+    let mut results_log = match &settings.results_log {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .map_err(|e| anyhow::anyhow!("Cannot create results log '{}': {}", path, e))?;
+            Some(ResultsLog::new(BufWriter::new(file))?)
+        }
+        None => None,
+    };
+
     // ── Spawn workers ──────────────────────────────────────────────────────
-    run(settings.clone(), benchmark_tx, Some(rx_sigint)).await?;
+    let teardown = run(settings.clone(), benchmark_tx, Some(rx_sigint)).await?;
 
     // ── Collect results ────────────────────────────────────────────────────
-    let mut report = Report::new(settings.clients);
+    let mut report = Report::new(settings.peak_clients())
+        .with_stages(&settings.stages)
+        .with_flows(&settings.flows)
+        .with_transactions(&settings.transactions())
+        .with_steps(&settings.step_metrics());
+    if settings.is_rate_limited() {
+        report = report.with_corrected_histogram();
+    }
     while let Some(value) = benchmark_rx.recv().await {
         match settings.verbose {
-            true => println!("{}", DisplayableBenchmarkResult(&value)),
+            true => println!(
+                "{}",
+                DisplayableBenchmarkResult(&value, report.step_name(&value))
+            ),
             false => {
-                if settings.duration.is_none() {
+                if settings.total_duration().is_none() {
                     pb.inc(1);
                 }
             }
         }
+        if let Some(log) = &mut results_log {
+            log.write(&value, report.step_name(&value))
+                .map_err(|e| anyhow::anyhow!("Cannot write results log: {}", e))?;
+        }
         // Update live-stats report if active
         if let Some(live) = &live_report {
             if let Ok(mut r) = live.lock() {
@@ -138,6 +209,13 @@ async fn run_benchmark(settings: Settings) -> Result<()> {
         report.add_result(value);
     }
     pb.finish_and_clear();
+    for failure in teardown.failures().await {
+        eprintln!("Warning: teardown failed: {}", failure);
+    }
+    if let Some(log) = results_log {
+        log.finish()
+            .map_err(|e| anyhow::anyhow!("Cannot write results log: {}", e))?;
+    }
 
     // ── Output results ─────────────────────────────────────────────────────
     write_output(&settings, &report)?;
@@ -185,13 +263,6 @@ fn write_output(settings: &Settings, report: &Report) -> Result<()> {
         }
     }
 
-    // Write per-request log if requested
-    if let Some(log_path) = &settings.results_log {
-        let mut log_file = std::fs::File::create(log_path)
-            .map_err(|e| anyhow::anyhow!("Cannot create results log '{}': {}", log_path, e))?;
-        write_results_log(report, &mut log_file);
-    }
-
     Ok(())
 }
 
@@ -199,14 +270,27 @@ fn write_output(settings: &Settings, report: &Report) -> Result<()> {
 // Banner
 // ---------------------------------------------------------------------------
 
-pub fn print_banner(settings: &Settings) {
-    let target_display = if settings.steps.is_empty() {
-        settings.target.clone()
-    } else {
+/// What the run hits: the target URL, or the size of the scenario.
+fn describe_target(settings: &Settings) -> String {
+    if !settings.flows.is_empty() {
+        format!("{} flows", settings.flows.len())
+    } else if !settings.steps.is_empty() {
         format!("{} steps", settings.steps.len())
-    };
+    } else {
+        settings.target.clone()
+    }
+}
+
+pub fn print_banner(settings: &Settings) {
+    let target_display = describe_target(settings);
 
     let banner = match settings.duration {
+        _ if !settings.stages.is_empty() => format!(
+            "kamehameha to {} in {} stages over {} seconds",
+            target_display,
+            settings.stages.len(),
+            settings.total_duration().unwrap_or(0)
+        ),
         None => format!(
             "kamehameha to {} with {} concurrent clients and {} total iterations",
             target_display, settings.clients, settings.requests
@@ -225,16 +309,28 @@ pub fn print_banner(settings: &Settings) {
         extras.push("insecure".yellow().to_string());
     }
     if let Some(rps) = settings.rps {
-        extras.push(format!("{}rps limit", rps));
+        match settings.executor {
+            Executor::Closed => extras.push(format!("{}rps limit", rps)),
+            Executor::ConstantArrivalRate => extras.push(format!("{}rps arrival rate", rps)),
+        }
     }
     if settings.auth.is_some() {
         extras.push("auth".to_string());
     }
+    if let Some(think_time) = &settings.think_time {
+        extras.push(format!("think time {}", think_time));
+    }
+    if let Some(seed) = settings.seed {
+        extras.push(format!("seed {}", seed));
+    }
 
     println!("{}", banner.cyan().bold());
     if !extras.is_empty() {
         println!("  [{}]", extras.join(", "));
     }
+    for (i, stage) in settings.stages.iter().enumerate() {
+        println!("  {}. {}", i + 1, stage);
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -242,7 +338,7 @@ pub fn print_banner(settings: &Settings) {
 // ---------------------------------------------------------------------------
 
 fn build_progress_bar(settings: &Settings) -> ProgressBar {
-    match settings.duration {
+    match settings.total_duration() {
         None => {
             let bar = ProgressBar::new(settings.requests as u64);
             bar.set_style(
@@ -281,20 +377,23 @@ fn build_progress_bar(settings: &Settings) -> ProgressBar {
 // Verbose display
 // ---------------------------------------------------------------------------
 
-struct DisplayableBenchmarkResult<'a>(&'a BenchmarkResult);
+/// A result for verbose output, with the name of the scenario step that sent it.
+struct DisplayableBenchmarkResult<'a>(&'a BenchmarkResult, Option<&'a str>);
 
 impl Display for DisplayableBenchmarkResult<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "[{} {} {} {}] {} {}{}",
+            "[{} {} {} {}] {}{} {}",
             "Client".bold().green(),
             self.0.num_client.to_string().bold().green(),
             "Iter".bold().green(),
             self.0.execution.to_string().bold().green(),
-            self.0.status.to_string().bold().yellow(),
-            self.0.duration.to_string().cyan(),
-            "ms".cyan(),
+            self.1
+                .map(|name| format!("{} ", name.cyan()))
+                .unwrap_or_default(),
+            self.0.outcome.to_string().bold().yellow(),
+            format_latency(self.0.duration_us as f64).cyan(),
         )
     }
 }
@@ -1,6 +1,40 @@
 use std::io::Write;
 
-use goku_core::benchmark::{Metrics, Report};
+use crate::capacity::CapacityReport;
+use goku_core::benchmark::{
+    format_bytes, format_latency, BenchmarkResult, ErrorKind, Metrics, Outcome, Phases, Report,
+};
+
+/// Percentiles shown in text output.
+const QUANTILES: [(&str, f64); 4] = [
+    ("p50 (median)", 0.50),
+    ("p95", 0.95),
+    ("p99", 0.99),
+    ("p99.9", 0.999),
+];
+
+fn corrected_quantile(r: &Report, quantile: f64) -> Option<u64> {
+    r.corrected_hist
+        .as_ref()
+        .map(|h| h.value_at_quantile(quantile))
+}
+
+/// Microseconds to whole milliseconds, for the `_ms` fields kept for compatibility.
+fn us_to_ms(us: u64) -> u64 {
+    us / 1_000
+}
+
+/// Exact status codes counted under a class of the breakdown: `2`, `4`, `5`, or `None` for
+/// the codes counted as other.
+fn codes_in_class(r: &Report, class: Option<u16>) -> impl Iterator<Item = (u16, u64)> + '_ {
+    r.status_codes
+        .iter()
+        .filter(move |(code, _)| match class {
+            Some(c) => *code / 100 == c,
+            None => !matches!(*code / 100, 2 | 4 | 5),
+        })
+        .map(|(code, count)| (*code, *count))
+}
 
 // ---------------------------------------------------------------------------
 // Text output (stdout or file)
@@ -14,68 +48,246 @@ pub fn print_text(r: &Report, out: &mut dyn Write) {
     let _ = writeln!(out);
 
     let _ = writeln!(out, "{:<20} {}", "Concurrency level", r.clients);
-    let _ = writeln!(
-        out,
-        "{:<20} {} seconds",
-        "Time taken",
-        elapsed.as_secs()
-    );
+    let _ = writeln!(out, "{:<20} {} seconds", "Time taken", elapsed.as_secs());
     let _ = writeln!(out, "{:<20} {}", "Total requests", r.hist.len());
+    if r.dropped > 0 {
+        let _ = writeln!(out, "{:<20} {}", "Dropped iterations", r.dropped);
+    }
     let _ = writeln!(
         out,
         "{:<20} {:.2} req/s",
         "Requests/sec",
         r.requests_per_second()
     );
-    let _ = writeln!(
-        out,
-        "{:<20} {:.2} ms",
-        "Mean",
-        r.hist.mean()
-    );
-    let _ = writeln!(out, "{:<20} {} ms", "Min", r.results.min());
-    let _ = writeln!(out, "{:<20} {} ms", "Max", r.results.max());
-    let _ = writeln!(
-        out,
-        "{:<20} {} ms",
-        "p50 (median)",
-        r.hist.value_at_quantile(0.50)
-    );
-    let _ = writeln!(
-        out,
-        "{:<20} {} ms",
-        "p95",
-        r.hist.value_at_quantile(0.95)
-    );
-    let _ = writeln!(
-        out,
-        "{:<20} {} ms",
-        "p99",
-        r.hist.value_at_quantile(0.99)
-    );
-    let _ = writeln!(
-        out,
-        "{:<20} {} ms",
-        "p99.9",
-        r.hist.value_at_quantile(0.999)
-    );
-
-    let _ = writeln!(out);
-    let _ = writeln!(out, "{}", "Status codes");
-    let _ = writeln!(out, "  2xx  {}", bd.success);
-    if bd.client_error > 0 {
-        let _ = writeln!(out, "  4xx  {}", bd.client_error);
+    if r.bytes_received > 0 || r.bytes_sent > 0 {
+        let _ = writeln!(
+            out,
+            "{:<20} {} ({:.2} MB/s)",
+            "Bytes received",
+            format_bytes(r.bytes_received),
+            r.received_mb_per_sec()
+        );
+        let _ = writeln!(
+            out,
+            "{:<20} {} ({:.2} MB/s)",
+            "Bytes sent",
+            format_bytes(r.bytes_sent),
+            r.sent_mb_per_sec()
+        );
     }
-    if bd.server_error > 0 {
-        let _ = writeln!(out, "  5xx  {}", bd.server_error);
+    let _ = writeln!(out, "{:<20} {}", "Mean", format_latency(r.hist.mean()));
+    let _ = writeln!(out, "{:<20} {}", "Min", format_latency(r.min() as f64));
+    let _ = writeln!(out, "{:<20} {}", "Max", format_latency(r.max() as f64));
+    for (label, q) in QUANTILES {
+        let _ = writeln!(
+            out,
+            "{:<20} {}",
+            label,
+            format_latency(r.hist.value_at_quantile(q) as f64)
+        );
     }
-    if bd.other > 0 {
-        let _ = writeln!(out, "  other  {}", bd.other);
+
+    if let Some(c) = &r.corrected_hist {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Corrected latency (from intended send time)");
+        let _ = writeln!(out, "{:<20} {}", "Mean", format_latency(c.mean()));
+        for (label, q) in QUANTILES {
+            let _ = writeln!(
+                out,
+                "{:<20} {}",
+                label,
+                format_latency(c.value_at_quantile(q) as f64)
+            );
+        }
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "Status codes");
+    let classes = [
+        ("2xx", bd.success, Some(2)),
+        ("4xx", bd.client_error, Some(4)),
+        ("5xx", bd.server_error, Some(5)),
+        ("other", bd.other, None),
+    ];
+    for (label, count, class) in classes {
+        if count == 0 && class != Some(2) {
+            continue;
+        }
+        let _ = writeln!(out, "  {}  {}", label, count);
+        for (code, n) in codes_in_class(r, class) {
+            let _ = writeln!(out, "    {}  {}", code, n);
+        }
     }
     if bd.network_error > 0 {
         let _ = writeln!(out, "  network errors  {}", bd.network_error);
     }
 
+    if !r.errors.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Errors");
+        for (kind, stats) in &r.errors {
+            let _ = writeln!(out, "  {}  {}", kind, stats.count);
+            for sample in &stats.samples {
+                let _ = writeln!(out, "    {}", sample);
+            }
+        }
+    }
+
+    if !r.checks.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Checks");
+        for c in &r.checks {
+            let mark = if c.failed == 0 { "✓" } else { "✗" };
+            let _ = writeln!(
+                out,
+                "  {} {}  passed {}  failed {}",
+                mark, c.name, c.passed, c.failed
+            );
+        }
+        let _ = writeln!(out, "  requests failing checks  {}", r.check_failures);
+    }
+
+    if !r.iteration_hist.is_empty() {
+        let h = &r.iteration_hist;
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Iterations (think time included)");
+        let _ = writeln!(
+            out,
+            "  {:<18} {} ({:.2}/s)",
+            "count",
+            h.len(),
+            r.iterations_per_second()
+        );
+        let _ = writeln!(out, "  {:<18} {}", "mean", format_latency(h.mean()));
+        for (label, q) in QUANTILES {
+            let _ = writeln!(
+                out,
+                "  {:<18} {}",
+                label,
+                format_latency(h.value_at_quantile(q) as f64)
+            );
+        }
+    }
+
+    if !r.stages.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "{:<24} {:>8} {:>9} {:>10} {:>10} {:>10} {:>10} {:>7}",
+            "Stages", "requests", "req/s", "mean", "p50", "p95", "p99", "errors"
+        );
+        for (i, s) in r.stages.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  {:<22} {:>8} {:>9.2} {:>10} {:>10} {:>10} {:>10} {:>7}",
+                format!("{}. {}", i + 1, s.name),
+                s.hist.len(),
+                s.requests_per_second(),
+                format_latency(s.hist.mean()),
+                format_latency(s.hist.value_at_quantile(0.50) as f64),
+                format_latency(s.hist.value_at_quantile(0.95) as f64),
+                format_latency(s.hist.value_at_quantile(0.99) as f64),
+                s.errors,
+            );
+        }
+    }
+
+    if !r.flows.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "{:<20} {:>7} {:>10} {:>8} {:>10} {:>10} {:>10} {:>7}",
+            "Flows", "weight", "iterations", "requests", "mean", "p95", "p99", "errors"
+        );
+        for f in &r.flows {
+            let _ = writeln!(
+                out,
+                "  {:<18} {:>6.1}% {:>10} {:>8} {:>10} {:>10} {:>10} {:>7}",
+                f.name,
+                flow_share(r, f.weight),
+                f.iteration_hist.len(),
+                f.hist.len(),
+                format_latency(f.hist.mean()),
+                format_latency(f.hist.value_at_quantile(0.95) as f64),
+                format_latency(f.hist.value_at_quantile(0.99) as f64),
+                f.errors,
+            );
+        }
+    }
+
+    if !r.steps.is_empty() {
+        // Names come last: unnamed steps are named after their URL, which can be long.
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Steps");
+        let _ = writeln!(
+            out,
+            "  {:>8} {:>10} {:>10} {:>10} {:>10} {:>6} {:>6} {:>6} {:>6} {:>8}  name",
+            "requests", "mean", "p50", "p95", "p99", "2xx", "4xx", "5xx", "other", "network"
+        );
+        for s in &r.steps {
+            let _ = writeln!(
+                out,
+                "  {:>8} {:>10} {:>10} {:>10} {:>10} {:>6} {:>6} {:>6} {:>6} {:>8}  {}",
+                s.hist.len(),
+                format_latency(s.hist.mean()),
+                format_latency(s.hist.value_at_quantile(0.50) as f64),
+                format_latency(s.hist.value_at_quantile(0.95) as f64),
+                format_latency(s.hist.value_at_quantile(0.99) as f64),
+                s.statuses.success,
+                s.statuses.client_error,
+                s.statuses.server_error,
+                s.statuses.other,
+                s.statuses.network_error,
+                s.name,
+            );
+        }
+    }
+
+    if !r.transactions.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "{:<20} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>7}",
+            "Transactions", "count", "mean", "p50", "p95", "p99", "max", "failed"
+        );
+        for t in &r.transactions {
+            let _ = writeln!(
+                out,
+                "  {:<18} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>7}",
+                t.name,
+                t.hist.len(),
+                format_latency(t.hist.mean()),
+                format_latency(t.hist.value_at_quantile(0.50) as f64),
+                format_latency(t.hist.value_at_quantile(0.95) as f64),
+                format_latency(t.hist.value_at_quantile(0.99) as f64),
+                format_latency(t.hist.max() as f64),
+                t.failed,
+            );
+        }
+    }
+
+    let phases: Vec<_> = r.recorded_phases().collect();
+    if !phases.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "{:<12} {:>8} {:>10} {:>10} {:>10} {:>10}",
+            "Phases", "count", "mean", "p50", "p95", "p99"
+        );
+        for (name, h) in phases {
+            let _ = writeln!(
+                out,
+                "  {:<10} {:>8} {:>10} {:>10} {:>10} {:>10}",
+                name,
+                h.len(),
+                format_latency(h.mean()),
+                format_latency(h.value_at_quantile(0.50) as f64),
+                format_latency(h.value_at_quantile(0.95) as f64),
+                format_latency(h.value_at_quantile(0.99) as f64),
+            );
+        }
+    }
+
     // ASCII latency histogram
     let buckets = r.latency_histogram(10);
     if !buckets.is_empty() {
@@ -117,85 +329,347 @@ pub fn print_text_colored(r: &Report) {
         "Total requests  ".yellow().bold(),
         r.hist.len().to_string().purple()
     );
+    if r.dropped > 0 {
+        println!(
+            "{} {} {}",
+            "Dropped         ".red().bold(),
+            r.dropped.to_string().purple(),
+            "(no free in-flight slot, consider more clients)".dimmed()
+        );
+    }
     println!(
         "{} {} {}",
         "Requests/sec    ".yellow().bold(),
         format!("{:.2}", r.requests_per_second()).purple(),
         "req/s".purple()
     );
+    if r.bytes_received > 0 || r.bytes_sent > 0 {
+        println!(
+            "{} {} {}",
+            "Bytes received  ".yellow().bold(),
+            format_bytes(r.bytes_received).purple(),
+            format!("({:.2} MB/s)", r.received_mb_per_sec()).purple()
+        );
+        println!(
+            "{} {} {}",
+            "Bytes sent      ".yellow().bold(),
+            format_bytes(r.bytes_sent).purple(),
+            format!("({:.2} MB/s)", r.sent_mb_per_sec()).purple()
+        );
+    }
     println!(
-        "{} {} {}",
+        "{} {}",
         "Mean            ".yellow().bold(),
-        format!("{:.2}", r.hist.mean()).purple(),
-        "ms".purple()
+        format_latency(r.hist.mean()).purple()
     );
     println!(
-        "{} {} {}",
+        "{} {}",
         "Min             ".yellow().bold(),
-        r.results.min().to_string().purple(),
-        "ms".purple()
+        format_latency(r.min() as f64).purple()
     );
     println!(
-        "{} {} {}",
+        "{} {}",
         "Max             ".yellow().bold(),
-        r.results.max().to_string().purple(),
-        "ms".purple()
-    );
-    println!(
-        "{} {} {}",
-        "p50 (median)    ".yellow().bold(),
-        r.hist.value_at_quantile(0.50).to_string().purple(),
-        "ms".purple()
-    );
-    println!(
-        "{} {} {}",
-        "p95             ".yellow().bold(),
-        r.hist.value_at_quantile(0.95).to_string().purple(),
-        "ms".purple()
-    );
-    println!(
-        "{} {} {}",
-        "p99             ".yellow().bold(),
-        r.hist.value_at_quantile(0.99).to_string().purple(),
-        "ms".purple()
-    );
-    println!(
-        "{} {} {}",
-        "p99.9           ".yellow().bold(),
-        r.hist.value_at_quantile(0.999).to_string().purple(),
-        "ms".purple()
+        format_latency(r.max() as f64).purple()
     );
+    for (label, q) in QUANTILES {
+        println!(
+            "{} {}",
+            format!("{:<16}", label).yellow().bold(),
+            format_latency(r.hist.value_at_quantile(q) as f64).purple()
+        );
+    }
+
+    if let Some(c) = &r.corrected_hist {
+        println!();
+        println!(
+            "{}",
+            "Corrected latency (from intended send time)"
+                .yellow()
+                .bold()
+        );
+        println!(
+            "{} {}",
+            "Mean            ".yellow().bold(),
+            format_latency(c.mean()).purple()
+        );
+        for (label, q) in QUANTILES {
+            println!(
+                "{} {}",
+                format!("{:<16}", label).yellow().bold(),
+                format_latency(c.value_at_quantile(q) as f64).purple()
+            );
+        }
+    }
 
     println!();
     println!("{}", "Status codes".yellow().bold());
-    println!("  {} {}", "2xx".green().bold(), bd.success.to_string().purple());
-    if bd.client_error > 0 {
+    let classes = [
+        ("2xx".green().bold(), bd.success, Some(2)),
+        ("4xx".yellow().bold(), bd.client_error, Some(4)),
+        ("5xx".red().bold(), bd.server_error, Some(5)),
+        ("other".cyan().bold(), bd.other, None),
+    ];
+    for (label, count, class) in classes {
+        if count == 0 && class != Some(2) {
+            continue;
+        }
+        println!("  {} {}", label, count.to_string().purple());
+        for (code, n) in codes_in_class(r, class) {
+            println!("    {} {}", code.to_string().bold(), n.to_string().purple());
+        }
+    }
+    if bd.network_error > 0 {
         println!(
             "  {} {}",
-            "4xx".yellow().bold(),
-            bd.client_error.to_string().purple()
+            "network errors".red().bold(),
+            bd.network_error.to_string().purple()
         );
     }
-    if bd.server_error > 0 {
+
+    if !r.errors.is_empty() {
+        println!();
+        println!("{}", "Errors".yellow().bold());
+        for (kind, stats) in &r.errors {
+            println!(
+                "  {} {}",
+                kind.to_string().red().bold(),
+                stats.count.to_string().purple()
+            );
+            for sample in &stats.samples {
+                println!("    {}", sample.dimmed());
+            }
+        }
+    }
+
+    if !r.checks.is_empty() {
+        println!();
+        println!("{}", "Checks".yellow().bold());
+        for c in &r.checks {
+            let mark = if c.failed == 0 {
+                "✓".green()
+            } else {
+                "✗".red()
+            };
+            println!(
+                "  {} {}  {} {}  {} {}",
+                mark,
+                c.name.cyan(),
+                "passed".dimmed(),
+                c.passed.to_string().purple(),
+                "failed".dimmed(),
+                c.failed.to_string().purple()
+            );
+        }
+        let failures = r.check_failures.to_string();
         println!(
-            "  {} {}",
-            "5xx".red().bold(),
-            bd.server_error.to_string().purple()
+            "  {}  {}",
+            "requests failing checks".yellow(),
+            if r.check_failures > 0 {
+                failures.red()
+            } else {
+                failures.purple()
+            }
         );
     }
-    if bd.other > 0 {
+
+    if !r.iteration_hist.is_empty() {
+        let h = &r.iteration_hist;
+        println!();
+        println!("{}", "Iterations (think time included)".yellow().bold());
+        println!(
+            "  {} {} {}",
+            format!("{:<18}", "count").yellow(),
+            h.len().to_string().purple(),
+            format!("({:.2}/s)", r.iterations_per_second()).purple()
+        );
         println!(
             "  {} {}",
-            "other".cyan().bold(),
-            bd.other.to_string().purple()
+            format!("{:<18}", "mean").yellow(),
+            format_latency(h.mean()).purple()
         );
+        for (label, q) in QUANTILES {
+            println!(
+                "  {} {}",
+                format!("{:<18}", label).yellow(),
+                format_latency(h.value_at_quantile(q) as f64).purple()
+            );
+        }
     }
-    if bd.network_error > 0 {
+
+    if !r.stages.is_empty() {
+        println!();
         println!(
-            "  {} {}",
-            "network errors".red().bold(),
-            bd.network_error.to_string().purple()
+            "{}",
+            format!(
+                "{:<24} {:>8} {:>9} {:>10} {:>10} {:>10} {:>10} {:>7}",
+                "Stages", "requests", "req/s", "mean", "p50", "p95", "p99", "errors"
+            )
+            .yellow()
+            .bold()
         );
+        for (i, s) in r.stages.iter().enumerate() {
+            let errors = format!("{:>7}", s.errors);
+            println!(
+                "  {} {} {}",
+                format!("{:<22}", format!("{}. {}", i + 1, s.name)).cyan(),
+                format!(
+                    "{:>8} {:>9.2} {:>10} {:>10} {:>10} {:>10}",
+                    s.hist.len(),
+                    s.requests_per_second(),
+                    format_latency(s.hist.mean()),
+                    format_latency(s.hist.value_at_quantile(0.50) as f64),
+                    format_latency(s.hist.value_at_quantile(0.95) as f64),
+                    format_latency(s.hist.value_at_quantile(0.99) as f64),
+                )
+                .purple(),
+                if s.errors > 0 {
+                    errors.red()
+                } else {
+                    errors.purple()
+                }
+            );
+        }
+    }
+
+    if !r.flows.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "{:<20} {:>7} {:>10} {:>8} {:>10} {:>10} {:>10} {:>7}",
+                "Flows", "weight", "iterations", "requests", "mean", "p95", "p99", "errors"
+            )
+            .yellow()
+            .bold()
+        );
+        for f in &r.flows {
+            let errors = format!("{:>7}", f.errors);
+            println!(
+                "  {} {} {}",
+                format!("{:<18}", f.name).cyan(),
+                format!(
+                    "{:>6.1}% {:>10} {:>8} {:>10} {:>10} {:>10}",
+                    flow_share(r, f.weight),
+                    f.iteration_hist.len(),
+                    f.hist.len(),
+                    format_latency(f.hist.mean()),
+                    format_latency(f.hist.value_at_quantile(0.95) as f64),
+                    format_latency(f.hist.value_at_quantile(0.99) as f64),
+                )
+                .purple(),
+                if f.errors > 0 {
+                    errors.red()
+                } else {
+                    errors.purple()
+                }
+            );
+        }
+    }
+
+    if !r.steps.is_empty() {
+        println!();
+        println!("{}", "Steps".yellow().bold());
+        println!(
+            "{}",
+            format!(
+                "  {:>8} {:>10} {:>10} {:>10} {:>10} {:>6} {:>6} {:>6} {:>6} {:>8}  name",
+                "requests", "mean", "p50", "p95", "p99", "2xx", "4xx", "5xx", "other", "network"
+            )
+            .yellow()
+        );
+        for s in &r.steps {
+            let errors = format!(
+                "{:>6} {:>6} {:>6} {:>8}",
+                s.statuses.client_error,
+                s.statuses.server_error,
+                s.statuses.other,
+                s.statuses.network_error
+            );
+            println!(
+                "  {} {}  {}",
+                format!(
+                    "{:>8} {:>10} {:>10} {:>10} {:>10} {:>6}",
+                    s.hist.len(),
+                    format_latency(s.hist.mean()),
+                    format_latency(s.hist.value_at_quantile(0.50) as f64),
+                    format_latency(s.hist.value_at_quantile(0.95) as f64),
+                    format_latency(s.hist.value_at_quantile(0.99) as f64),
+                    s.statuses.success,
+                )
+                .purple(),
+                if s.statuses.errors() > 0 {
+                    errors.red()
+                } else {
+                    errors.purple()
+                },
+                s.name.cyan()
+            );
+        }
+    }
+
+    if !r.transactions.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "{:<20} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>7}",
+                "Transactions", "count", "mean", "p50", "p95", "p99", "max", "failed"
+            )
+            .yellow()
+            .bold()
+        );
+        for t in &r.transactions {
+            let failed = format!("{:>7}", t.failed);
+            println!(
+                "  {} {} {}",
+                format!("{:<18}", t.name).cyan(),
+                format!(
+                    "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                    t.hist.len(),
+                    format_latency(t.hist.mean()),
+                    format_latency(t.hist.value_at_quantile(0.50) as f64),
+                    format_latency(t.hist.value_at_quantile(0.95) as f64),
+                    format_latency(t.hist.value_at_quantile(0.99) as f64),
+                    format_latency(t.hist.max() as f64),
+                )
+                .purple(),
+                if t.failed > 0 {
+                    failed.red()
+                } else {
+                    failed.purple()
+                }
+            );
+        }
+    }
+
+    let phases: Vec<_> = r.recorded_phases().collect();
+    if !phases.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "{:<12} {:>8} {:>10} {:>10} {:>10} {:>10}",
+                "Phases", "count", "mean", "p50", "p95", "p99"
+            )
+            .yellow()
+            .bold()
+        );
+        for (name, h) in phases {
+            println!(
+                "  {} {} {}",
+                format!("{:<10}", name).cyan(),
+                format!("{:>8}", h.len()).purple(),
+                format!(
+                    "{:>10} {:>10} {:>10} {:>10}",
+                    format_latency(h.mean()),
+                    format_latency(h.value_at_quantile(0.50) as f64),
+                    format_latency(h.value_at_quantile(0.95) as f64),
+                    format_latency(h.value_at_quantile(0.99) as f64),
+                )
+                .purple()
+            );
+        }
     }
 
     // ASCII latency histogram
@@ -206,8 +680,7 @@ pub fn print_text_colored(r: &Report) {
         let max_count = buckets.iter().map(|(_, c)| *c).max().unwrap_or(1).max(1);
         let bar_width = 40usize;
         for (label, count) in &buckets {
-            let filled =
-                ((*count as f64 / max_count as f64) * bar_width as f64).round() as usize;
+            let filled = ((*count as f64 / max_count as f64) * bar_width as f64).round() as usize;
             let bar: String = "█".repeat(filled);
             println!(
                 "  {:>8}  {}  {}",
@@ -226,55 +699,301 @@ pub fn print_text_colored(r: &Report) {
 pub fn print_json(r: &Report, out: &mut dyn Write) {
     let elapsed = r.start.elapsed().as_secs_f64();
     let bd = r.status_breakdown();
-    let min = r.results.iter().map(|x| x.duration).min().unwrap_or(0);
-    let max = r.results.iter().map(|x| x.duration).max().unwrap_or(0);
+    let min = r.min();
+    let max = r.max();
+    let round2 = |v: f64| format!("{:.2}", v).parse::<f64>().unwrap_or(0.0);
 
     let data = serde_json::json!({
         "concurrency": r.clients,
         "duration_secs": format!("{:.3}", elapsed).parse::<f64>().unwrap_or(0.0),
         "total_requests": r.hist.len(),
-        "requests_per_sec": format!("{:.2}", r.requests_per_second()).parse::<f64>().unwrap_or(0.0),
-        "mean_ms": format!("{:.2}", r.hist.mean()).parse::<f64>().unwrap_or(0.0),
-        "min_ms": min,
-        "max_ms": max,
-        "p50_ms": r.hist.value_at_quantile(0.50),
-        "p95_ms": r.hist.value_at_quantile(0.95),
-        "p99_ms": r.hist.value_at_quantile(0.99),
-        "p999_ms": r.hist.value_at_quantile(0.999),
+        "requests_per_sec": round2(r.requests_per_second()),
+        "mean_ms": round2(r.hist.mean() / 1_000.0),
+        "min_ms": us_to_ms(min),
+        "max_ms": us_to_ms(max),
+        "p50_ms": us_to_ms(r.hist.value_at_quantile(0.50)),
+        "p95_ms": us_to_ms(r.hist.value_at_quantile(0.95)),
+        "p99_ms": us_to_ms(r.hist.value_at_quantile(0.99)),
+        "p999_ms": us_to_ms(r.hist.value_at_quantile(0.999)),
+        "mean_us": round2(r.hist.mean()),
+        "min_us": min,
+        "max_us": max,
+        "p50_us": r.hist.value_at_quantile(0.50),
+        "p95_us": r.hist.value_at_quantile(0.95),
+        "p99_us": r.hist.value_at_quantile(0.99),
+        "p999_us": r.hist.value_at_quantile(0.999),
         "status_2xx": bd.success,
         "status_4xx": bd.client_error,
         "status_5xx": bd.server_error,
         "status_other": bd.other,
         "network_errors": bd.network_error,
+        "status_codes": status_codes_json(r),
+        "errors": errors_json(r),
+        "dropped_iterations": r.dropped,
+        "check_failures": r.check_failures,
+        "bytes_received": r.bytes_received,
+        "bytes_sent": r.bytes_sent,
+        "received_mb_per_sec": round2(r.received_mb_per_sec()),
+        "sent_mb_per_sec": round2(r.sent_mb_per_sec()),
+        "corrected_mean_ms": r.corrected_hist.as_ref().map(|h| round2(h.mean() / 1_000.0)),
+        "corrected_p50_ms": corrected_quantile(r, 0.50).map(us_to_ms),
+        "corrected_p95_ms": corrected_quantile(r, 0.95).map(us_to_ms),
+        "corrected_p99_ms": corrected_quantile(r, 0.99).map(us_to_ms),
+        "corrected_p999_ms": corrected_quantile(r, 0.999).map(us_to_ms),
+        "corrected_mean_us": r.corrected_hist.as_ref().map(|h| round2(h.mean())),
+        "corrected_p50_us": corrected_quantile(r, 0.50),
+        "corrected_p95_us": corrected_quantile(r, 0.95),
+        "corrected_p99_us": corrected_quantile(r, 0.99),
+        "corrected_p999_us": corrected_quantile(r, 0.999),
+        "phases": phases_json(r),
+        "checks": checks_json(r),
+        "stages": stages_json(r),
+        "flows": flows_json(r),
+        "transactions": transactions_json(r),
+        "steps": steps_json(r),
+        "iterations": iterations_json(r),
     });
 
-    let json_str = serde_json::to_string_pretty(&data)
-        .unwrap_or_else(|e| format!("{{\"error\": \"{e}\"}}"));
+    let json_str =
+        serde_json::to_string_pretty(&data).unwrap_or_else(|e| format!("{{\"error\": \"{e}\"}}"));
     let _ = writeln!(out, "{}", json_str);
 }
 
+/// Responses per exact status code, keyed by the code.
+fn status_codes_json(r: &Report) -> serde_json::Value {
+    let codes: serde_json::Map<String, serde_json::Value> = r
+        .status_codes
+        .iter()
+        .map(|(code, count)| (code.to_string(), serde_json::json!(count)))
+        .collect();
+    serde_json::Value::Object(codes)
+}
+
+/// Requests without a response per error kind, with a few distinct messages of each.
+fn errors_json(r: &Report) -> serde_json::Value {
+    let errors: serde_json::Map<String, serde_json::Value> = r
+        .errors
+        .iter()
+        .map(|(kind, stats)| {
+            let stats = serde_json::json!({
+                "count": stats.count,
+                "samples": stats.samples,
+            });
+            (kind.to_string(), stats)
+        })
+        .collect();
+    serde_json::Value::Object(errors)
+}
+
+/// Per-phase latency summary keyed by phase name (only phases that were observed).
+fn phases_json(r: &Report) -> serde_json::Value {
+    let phases: serde_json::Map<String, serde_json::Value> = r
+        .recorded_phases()
+        .map(|(name, h)| {
+            let stats = serde_json::json!({
+                "count": h.len(),
+                "mean_us": format!("{:.2}", h.mean()).parse::<f64>().unwrap_or(0.0),
+                "p50_us": h.value_at_quantile(0.50),
+                "p95_us": h.value_at_quantile(0.95),
+                "p99_us": h.value_at_quantile(0.99),
+                "max_us": h.max(),
+            });
+            (name.to_string(), stats)
+        })
+        .collect();
+    serde_json::Value::Object(phases)
+}
+
+/// Pass/fail counts per check, in the order checks were first seen.
+fn checks_json(r: &Report) -> serde_json::Value {
+    r.checks
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "name": c.name,
+                "passed": c.passed,
+                "failed": c.failed,
+            })
+        })
+        .collect()
+}
+
+/// Iteration count, rate and duration summary, or `null` when iterations were not timed.
+fn iterations_json(r: &Report) -> serde_json::Value {
+    let h = &r.iteration_hist;
+    if h.is_empty() {
+        return serde_json::Value::Null;
+    }
+    serde_json::json!({
+        "count": h.len(),
+        "per_sec": format!("{:.2}", r.iterations_per_second()).parse::<f64>().unwrap_or(0.0),
+        "mean_us": format!("{:.2}", h.mean()).parse::<f64>().unwrap_or(0.0),
+        "p50_us": h.value_at_quantile(0.50),
+        "p95_us": h.value_at_quantile(0.95),
+        "p99_us": h.value_at_quantile(0.99),
+        "max_us": h.max(),
+    })
+}
+
+/// Latency summary of each load stage, in profile order (empty without stages).
+fn stages_json(r: &Report) -> serde_json::Value {
+    r.stages
+        .iter()
+        .map(|s| {
+            serde_json::json!({
+                "name": s.name,
+                "duration_secs": s.duration_secs,
+                "total_requests": s.hist.len(),
+                "requests_per_sec": format!("{:.2}", s.requests_per_second()).parse::<f64>().unwrap_or(0.0),
+                "mean_us": format!("{:.2}", s.hist.mean()).parse::<f64>().unwrap_or(0.0),
+                "p50_us": s.hist.value_at_quantile(0.50),
+                "p95_us": s.hist.value_at_quantile(0.95),
+                "p99_us": s.hist.value_at_quantile(0.99),
+                "errors": s.errors,
+                "check_failures": s.check_failures,
+                "dropped_iterations": s.dropped,
+            })
+        })
+        .collect()
+}
+
+/// Iteration and latency summary of each scenario flow (empty without flows).
+fn flows_json(r: &Report) -> serde_json::Value {
+    r.flows
+        .iter()
+        .map(|f| {
+            serde_json::json!({
+                "name": f.name,
+                "weight": f.weight,
+                "iterations": f.iteration_hist.len(),
+                "total_requests": f.hist.len(),
+                "mean_us": format!("{:.2}", f.hist.mean()).parse::<f64>().unwrap_or(0.0),
+                "p50_us": f.hist.value_at_quantile(0.50),
+                "p95_us": f.hist.value_at_quantile(0.95),
+                "p99_us": f.hist.value_at_quantile(0.99),
+                "iteration_p95_us": f.iteration_hist.value_at_quantile(0.95),
+                "errors": f.errors,
+                "check_failures": f.check_failures,
+            })
+        })
+        .collect()
+}
+
+/// Latency and status summary of each scenario step (empty for a single target).
+fn steps_json(r: &Report) -> serde_json::Value {
+    r.steps
+        .iter()
+        .map(|s| {
+            serde_json::json!({
+                "name": s.name,
+                "target": s.target,
+                "total_requests": s.hist.len(),
+                "mean_us": format!("{:.2}", s.hist.mean()).parse::<f64>().unwrap_or(0.0),
+                "min_us": s.hist.min(),
+                "max_us": s.hist.max(),
+                "p50_us": s.hist.value_at_quantile(0.50),
+                "p95_us": s.hist.value_at_quantile(0.95),
+                "p99_us": s.hist.value_at_quantile(0.99),
+                "p999_us": s.hist.value_at_quantile(0.999),
+                "status_2xx": s.statuses.success,
+                "status_4xx": s.statuses.client_error,
+                "status_5xx": s.statuses.server_error,
+                "status_other": s.statuses.other,
+                "network_errors": s.statuses.network_error,
+                "check_failures": s.check_failures,
+            })
+        })
+        .collect()
+}
+
+/// Duration summary of each scenario transaction (empty without transactions).
+fn transactions_json(r: &Report) -> serde_json::Value {
+    r.transactions
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "name": t.name,
+                "count": t.hist.len(),
+                "mean_us": format!("{:.2}", t.hist.mean()).parse::<f64>().unwrap_or(0.0),
+                "p50_us": t.hist.value_at_quantile(0.50),
+                "p95_us": t.hist.value_at_quantile(0.95),
+                "p99_us": t.hist.value_at_quantile(0.99),
+                "max_us": t.hist.max(),
+                "failed": t.failed,
+            })
+        })
+        .collect()
+}
+
+/// Share of iterations a flow's weight asks for, in percent.
+fn flow_share(r: &Report, weight: u32) -> f64 {
+    let total: u32 = r.flows.iter().map(|f| f.weight).sum();
+    weight as f64 * 100.0 / total.max(1) as f64
+}
+
 // ---------------------------------------------------------------------------
 // CSV output
 // ---------------------------------------------------------------------------
 
+/// Writes one CSV table with a fixed set of columns. The first row covers the whole run
+/// (`scope` = `run`); it is followed by one row per scenario step, transaction and exact
+/// status code, identified by `scope` and `name`, which only fill the columns that apply.
 pub fn print_csv(r: &Report, out: &mut dyn Write) {
     let elapsed = r.start.elapsed().as_secs_f64();
     let bd = r.status_breakdown();
-    let min = r.results.iter().map(|x| x.duration).min().unwrap_or(0);
-    let max = r.results.iter().map(|x| x.duration).max().unwrap_or(0);
+    let min = r.min();
+    let max = r.max();
 
-    let _ = writeln!(
-        out,
+    let header = format!(
         "concurrency,duration_secs,total_requests,requests_per_sec,mean_ms,min_ms,max_ms,\
-p50_ms,p95_ms,p99_ms,p999_ms,status_2xx,status_4xx,status_5xx,status_other,network_errors"
+p50_ms,p95_ms,p99_ms,p999_ms,status_2xx,status_4xx,status_5xx,status_other,network_errors,\
+dropped_iterations,corrected_p50_ms,corrected_p95_ms,corrected_p99_ms,corrected_p999_ms,\
+mean_us,min_us,max_us,p50_us,p95_us,p99_us,p999_us,\
+corrected_p50_us,corrected_p95_us,corrected_p99_us,corrected_p999_us,{},\
+bytes_received,bytes_sent,received_mb_per_sec,sent_mb_per_sec,check_failures,\
+iterations,iteration_p50_us,iteration_p95_us,iteration_p99_us,{},scope,name,target,count,failed",
+        Phases::NAMES
+            .iter()
+            .map(|p| format!("{p}_p50_us,{p}_p95_us,{p}_p99_us"))
+            .collect::<Vec<_>>()
+            .join(","),
+        ErrorKind::ALL
+            .iter()
+            .map(|kind| format!("{kind}_errors"))
+            .collect::<Vec<_>>()
+            .join(","),
     );
+    let _ = writeln!(out, "{}", header);
+    let corrected = |q: f64, to_unit: fn(u64) -> u64| {
+        corrected_quantile(r, q)
+            .map(|v| to_unit(v).to_string())
+            .unwrap_or_default()
+    };
     let _ = writeln!(
         out,
-        "{},{:.3},{},{:.2},{:.2},{},{},{},{},{},{},{},{},{},{},{}",
+        "{},{:.3},{},{:.2},{:.2},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},\
+{:.2},{},{},{},{},{},{},{},{},{},{},{},{},{},{:.2},{:.2},{},{},{},{},run,,,,",
         r.clients,
         elapsed,
         r.hist.len(),
         r.requests_per_second(),
+        r.hist.mean() / 1_000.0,
+        us_to_ms(min),
+        us_to_ms(max),
+        us_to_ms(r.hist.value_at_quantile(0.50)),
+        us_to_ms(r.hist.value_at_quantile(0.95)),
+        us_to_ms(r.hist.value_at_quantile(0.99)),
+        us_to_ms(r.hist.value_at_quantile(0.999)),
+        bd.success,
+        bd.client_error,
+        bd.server_error,
+        bd.other,
+        bd.network_error,
+        r.dropped,
+        corrected(0.50, us_to_ms),
+        corrected(0.95, us_to_ms),
+        corrected(0.99, us_to_ms),
+        corrected(0.999, us_to_ms),
         r.hist.mean(),
         min,
         max,
@@ -282,29 +1001,276 @@ p50_ms,p95_ms,p99_ms,p999_ms,status_2xx,status_4xx,status_5xx,status_other,netwo
         r.hist.value_at_quantile(0.95),
         r.hist.value_at_quantile(0.99),
         r.hist.value_at_quantile(0.999),
-        bd.success,
-        bd.client_error,
-        bd.server_error,
-        bd.other,
-        bd.network_error,
+        corrected(0.50, std::convert::identity),
+        corrected(0.95, std::convert::identity),
+        corrected(0.99, std::convert::identity),
+        corrected(0.999, std::convert::identity),
+        r.phase_hists
+            .iter()
+            .map(|h| {
+                if h.is_empty() {
+                    ",,".to_string()
+                } else {
+                    format!(
+                        "{},{},{}",
+                        h.value_at_quantile(0.50),
+                        h.value_at_quantile(0.95),
+                        h.value_at_quantile(0.99)
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        r.bytes_received,
+        r.bytes_sent,
+        r.received_mb_per_sec(),
+        r.sent_mb_per_sec(),
+        r.check_failures,
+        r.iteration_hist.len(),
+        if r.iteration_hist.is_empty() {
+            ",,".to_string()
+        } else {
+            format!(
+                "{},{},{}",
+                r.iteration_hist.value_at_quantile(0.50),
+                r.iteration_hist.value_at_quantile(0.95),
+                r.iteration_hist.value_at_quantile(0.99)
+            )
+        },
+        ErrorKind::ALL
+            .iter()
+            .map(|kind| r.errors.get(kind).map_or(0, |e| e.count).to_string())
+            .collect::<Vec<_>>()
+            .join(","),
     );
+
+    let columns: Vec<&str> = header.split(',').collect();
+    let row = |values: &[(&str, String)]| {
+        columns
+            .iter()
+            .map(|c| values.iter().find(|(k, _)| k == c).map(|(_, v)| v.as_str()))
+            .map(Option::unwrap_or_default)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    macro_rules! latency {
+        ($h:expr) => {
+            [
+                ("mean_us", format!("{:.2}", $h.mean())),
+                ("min_us", $h.min().to_string()),
+                ("max_us", $h.max().to_string()),
+                ("p50_us", $h.value_at_quantile(0.50).to_string()),
+                ("p95_us", $h.value_at_quantile(0.95).to_string()),
+                ("p99_us", $h.value_at_quantile(0.99).to_string()),
+                ("p999_us", $h.value_at_quantile(0.999).to_string()),
+            ]
+        };
+    }
+    for s in &r.steps {
+        let mut values = vec![
+            ("scope", "step".to_string()),
+            ("name", csv_field(&s.name)),
+            ("target", csv_field(&s.target)),
+            ("total_requests", s.hist.len().to_string()),
+            ("status_2xx", s.statuses.success.to_string()),
+            ("status_4xx", s.statuses.client_error.to_string()),
+            ("status_5xx", s.statuses.server_error.to_string()),
+            ("status_other", s.statuses.other.to_string()),
+            ("network_errors", s.statuses.network_error.to_string()),
+            ("check_failures", s.check_failures.to_string()),
+        ];
+        values.extend(latency!(s.hist));
+        let _ = writeln!(out, "{}", row(&values));
+    }
+    for t in &r.transactions {
+        let mut values = vec![
+            ("scope", "transaction".to_string()),
+            ("name", csv_field(&t.name)),
+            ("count", t.hist.len().to_string()),
+            ("failed", t.failed.to_string()),
+        ];
+        values.extend(latency!(t.hist));
+        let _ = writeln!(out, "{}", row(&values));
+    }
+    for (code, count) in &r.status_codes {
+        let values = [
+            ("scope", "status_code".to_string()),
+            ("name", code.to_string()),
+            ("total_requests", count.to_string()),
+        ];
+        let _ = writeln!(out, "{}", row(&values));
+    }
+}
+
+/// Quotes a CSV field when it contains a delimiter, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Results log (per-request CSV)
 // ---------------------------------------------------------------------------
 
-pub fn write_results_log(r: &Report, out: &mut dyn Write) {
-    let _ = writeln!(out, "timestamp_ms,num_client,execution,status,latency_ms");
-    for result in &r.results {
-        let _ = writeln!(
+/// Per-request CSV log, written one row per result as results arrive so it never has to be
+/// held in memory.
+pub struct ResultsLog<W: Write> {
+    out: W,
+}
+
+impl<W: Write> ResultsLog<W> {
+    /// Starts the log with its header line.
+    pub fn new(mut out: W) -> std::io::Result<Self> {
+        writeln!(
             out,
-            "{},{},{},{},{}",
+            "timestamp_ms,num_client,execution,status,latency_ms,latency_us,\
+dns_us,connect_us,tls_us,ttfb_us,body_us,bytes_received,bytes_sent,failed_checks,\
+think_time_us,iteration_us,step,error"
+        )?;
+        Ok(ResultsLog { out })
+    }
+
+    /// Appends `result`, sent by the step named `step`. Dropped iterations sent no request and
+    /// are left out.
+    pub fn write(&mut self, result: &BenchmarkResult, step: Option<&str>) -> std::io::Result<()> {
+        let (status, error) = match &result.outcome {
+            Outcome::Status(code) => (code.to_string(), String::new()),
+            Outcome::Error { kind, message } => (kind.to_string(), csv_field(message)),
+            Outcome::Dropped => return Ok(()),
+        };
+        let phases: Vec<String> = result
+            .phases
+            .values()
+            .iter()
+            .map(|v| v.map(|us| us.to_string()).unwrap_or_default())
+            .collect();
+        writeln!(
+            self.out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
             result.timestamp_ms,
             result.num_client,
             result.execution,
-            result.status,
-            result.duration,
+            status,
+            us_to_ms(result.duration_us),
+            result.duration_us,
+            phases.join(","),
+            result.bytes_received,
+            result.bytes_sent,
+            result.checks.iter().filter(|c| !c.passed).count(),
+            result.think_time_us,
+            result
+                .iteration_us
+                .map(|us| us.to_string())
+                .unwrap_or_default(),
+            step.map(csv_field).unwrap_or_default(),
+            error,
+        )
+    }
+
+    /// Flushes buffered rows and returns the writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Capacity search
+// ---------------------------------------------------------------------------
+
+pub fn print_capacity_text(c: &CapacityReport, out: &mut dyn Write) {
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "{:<6} {:>10} {:>12} {:>10} {:>10} {:>8}",
+        "Probe", "rate", "req/s", "p99", "errors", "result"
+    );
+    for (i, p) in c.probes.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{:<6} {:>10} {:>12.2} {:>10} {:>9.2}% {:>8}",
+            i + 1,
+            p.target_rps,
+            p.achieved_rps,
+            format_latency(p.p99_us as f64),
+            p.error_rate * 100.0,
+            if p.passed { "pass" } else { "fail" }
+        );
+    }
+
+    let _ = writeln!(out);
+    let thresholds = match c.max_p99_ms {
+        Some(ms) => format!("p99 <= {}ms, errors <= {}%", ms, c.max_error_rate),
+        None => format!("errors <= {}%", c.max_error_rate),
+    };
+    let _ = writeln!(out, "{:<28} {}", "Thresholds", thresholds);
+    match c.max_sustainable_rps {
+        Some(rps) => {
+            let _ = writeln!(out, "{:<28} {} req/s", "Max sustainable throughput", rps);
+        }
+        None => {
+            let _ = writeln!(
+                out,
+                "{:<28} none (every probe failed)",
+                "Max sustainable throughput"
+            );
+        }
+    }
+    if let Some(rps) = c.breaking_rps {
+        let _ = writeln!(out, "{:<28} {} req/s", "Breaking point", rps);
+    }
+    if c.interrupted {
+        let _ = writeln!(out, "Search interrupted before converging");
+    }
+}
+
+pub fn print_capacity_json(c: &CapacityReport, out: &mut dyn Write) {
+    let probes: Vec<serde_json::Value> = c
+        .probes
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "target_rps": p.target_rps,
+                "requests_per_sec": format!("{:.2}", p.achieved_rps).parse::<f64>().unwrap_or(0.0),
+                "total_requests": p.requests,
+                "p99_us": p.p99_us,
+                "error_rate": p.error_rate,
+                "passed": p.passed,
+            })
+        })
+        .collect();
+    let data = serde_json::json!({
+        "max_sustainable_rps": c.max_sustainable_rps,
+        "breaking_rps": c.breaking_rps,
+        "max_p99_ms": c.max_p99_ms,
+        "max_error_rate": c.max_error_rate,
+        "interrupted": c.interrupted,
+        "probes": probes,
+    });
+    let json_str =
+        serde_json::to_string_pretty(&data).unwrap_or_else(|e| format!("{{\"error\": \"{e}\"}}"));
+    let _ = writeln!(out, "{}", json_str);
+}
+
+pub fn print_capacity_csv(c: &CapacityReport, out: &mut dyn Write) {
+    let _ = writeln!(
+        out,
+        "probe,target_rps,requests_per_sec,total_requests,p99_us,error_rate,passed"
+    );
+    for (i, p) in c.probes.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{},{},{:.2},{},{},{:.4},{}",
+            i + 1,
+            p.target_rps,
+            p.achieved_rps,
+            p.requests,
+            p.p99_us,
+            p.error_rate,
+            p.passed
         );
     }
 }
@@ -331,6 +1297,57 @@ pub struct RunSnapshot {
     pub status_4xx: Option<u64>,
     pub status_5xx: Option<u64>,
     pub network_errors: Option<u64>,
+    pub dropped_iterations: Option<u64>,
+    pub check_failures: Option<u64>,
+    pub bytes_received: Option<u64>,
+    pub bytes_sent: Option<u64>,
+    pub received_mb_per_sec: Option<f64>,
+    pub sent_mb_per_sec: Option<f64>,
+    pub corrected_p50_ms: Option<u64>,
+    pub corrected_p95_ms: Option<u64>,
+    pub corrected_p99_ms: Option<u64>,
+    pub corrected_p999_ms: Option<u64>,
+    pub mean_us: Option<f64>,
+    pub min_us: Option<u64>,
+    pub max_us: Option<u64>,
+    pub p50_us: Option<u64>,
+    pub p95_us: Option<u64>,
+    pub p99_us: Option<u64>,
+    pub p999_us: Option<u64>,
+    pub corrected_p50_us: Option<u64>,
+    pub corrected_p95_us: Option<u64>,
+    pub corrected_p99_us: Option<u64>,
+    pub corrected_p999_us: Option<u64>,
+    /// Per-step metrics of a scenario run; empty for a single target or older result files.
+    #[serde(default)]
+    pub steps: Vec<StepSnapshot>,
+}
+
+/// The metrics of one scenario step in a saved JSON report.
+#[allow(dead_code)]
+#[derive(Debug, serde::Deserialize)]
+pub struct StepSnapshot {
+    pub name: String,
+    pub target: Option<String>,
+    pub total_requests: Option<u64>,
+    pub mean_us: Option<f64>,
+    pub p50_us: Option<u64>,
+    pub p95_us: Option<u64>,
+    pub p99_us: Option<u64>,
+    pub status_2xx: Option<u64>,
+    pub status_4xx: Option<u64>,
+    pub status_5xx: Option<u64>,
+    pub network_errors: Option<u64>,
+    pub check_failures: Option<u64>,
+}
+
+impl StepSnapshot {
+    /// Requests that got a 4xx/5xx response or no response.
+    fn errors(&self) -> u64 {
+        self.status_4xx.unwrap_or(0)
+            + self.status_5xx.unwrap_or(0)
+            + self.network_errors.unwrap_or(0)
+    }
 }
 
 pub fn print_comparison(baseline: &RunSnapshot, candidate: &RunSnapshot) {
@@ -342,8 +1359,16 @@ pub fn print_comparison(baseline: &RunSnapshot, candidate: &RunSnapshot) {
         }
         let pct = (cand - base) / base * 100.0;
         let label = format!("{:+.1}%", pct);
-        let improvement = if lower_is_better { pct < 0.0 } else { pct > 0.0 };
-        let regression = if lower_is_better { pct > 5.0 } else { pct < -5.0 };
+        let improvement = if lower_is_better {
+            pct < 0.0
+        } else {
+            pct > 0.0
+        };
+        let regression = if lower_is_better {
+            pct > 5.0
+        } else {
+            pct < -5.0
+        };
         if improvement {
             label.green().to_string()
         } else if regression {
@@ -392,19 +1417,119 @@ pub fn print_comparison(baseline: &RunSnapshot, candidate: &RunSnapshot) {
         };
     }
 
+    // Latencies are compared in ms. The `_us` fields are preferred when both runs have them so
+    // sub-millisecond differences are not lost; older result files only carry `_ms`.
+    macro_rules! row_latency {
+        ($label:expr, $us:ident, $ms:ident) => {
+            let (base, cand) = match (baseline.$us, candidate.$us) {
+                (Some(b), Some(c)) => (b as f64 / 1_000.0, c as f64 / 1_000.0),
+                _ => (
+                    baseline.$ms.unwrap_or(0) as f64,
+                    candidate.$ms.unwrap_or(0) as f64,
+                ),
+            };
+            println!(
+                "{:<22} {:>12.3} {:>12.3} {:>12}",
+                $label,
+                base,
+                cand,
+                fmt_pct(base, cand, true)
+            );
+        };
+    }
+
     row_f64!("Requests/sec", requests_per_sec, false);
-    row_f64!("Mean (ms)", mean_ms, true);
-    row_u64!("p50 (ms)", p50_ms, true);
-    row_u64!("p95 (ms)", p95_ms, true);
-    row_u64!("p99 (ms)", p99_ms, true);
-    row_u64!("p99.9 (ms)", p999_ms, true);
-    row_u64!("Min (ms)", min_ms, true);
-    row_u64!("Max (ms)", max_ms, true);
+    let (base_mean, cand_mean) = match (baseline.mean_us, candidate.mean_us) {
+        (Some(b), Some(c)) => (b / 1_000.0, c / 1_000.0),
+        _ => (
+            baseline.mean_ms.unwrap_or(0.0),
+            candidate.mean_ms.unwrap_or(0.0),
+        ),
+    };
+    println!(
+        "{:<22} {:>12.3} {:>12.3} {:>12}",
+        "Mean (ms)",
+        base_mean,
+        cand_mean,
+        fmt_pct(base_mean, cand_mean, true)
+    );
+    row_latency!("p50 (ms)", p50_us, p50_ms);
+    row_latency!("p95 (ms)", p95_us, p95_ms);
+    row_latency!("p99 (ms)", p99_us, p99_ms);
+    row_latency!("p99.9 (ms)", p999_us, p999_ms);
+    row_latency!("Min (ms)", min_us, min_ms);
+    row_latency!("Max (ms)", max_us, max_ms);
+    if baseline.received_mb_per_sec.unwrap_or(0.0) > 0.0
+        || candidate.received_mb_per_sec.unwrap_or(0.0) > 0.0
+    {
+        row_f64!("Received (MB/s)", received_mb_per_sec, false);
+    }
     row_u64!("Total requests", total_requests, false);
     row_u64!("2xx", status_2xx, false);
     row_u64!("4xx", status_4xx, true);
     row_u64!("5xx", status_5xx, true);
     row_u64!("Network errors", network_errors, true);
+    row_u64!("Dropped iterations", dropped_iterations, true);
+    if baseline.check_failures.is_some() || candidate.check_failures.is_some() {
+        row_u64!("Check failures", check_failures, true);
+    }
+    if baseline.corrected_p99_ms.is_some() || candidate.corrected_p99_ms.is_some() {
+        row_latency!("Corrected p50 (ms)", corrected_p50_us, corrected_p50_ms);
+        row_latency!("Corrected p95 (ms)", corrected_p95_us, corrected_p95_ms);
+        row_latency!("Corrected p99 (ms)", corrected_p99_us, corrected_p99_ms);
+        row_latency!("Corrected p99.9 (ms)", corrected_p999_us, corrected_p999_ms);
+    }
+
+    // Steps are matched by name; a step only present in one run cannot be compared.
+    for base in &baseline.steps {
+        let Some(cand) = candidate.steps.iter().find(|s| s.name == base.name) else {
+            println!();
+            println!("{} {}", base.name.cyan(), "(only in baseline)".dimmed());
+            continue;
+        };
+        println!();
+        println!("{}", base.name.cyan());
+        let row = |label: &str, base: f64, cand: f64, precision: usize, lower: bool| {
+            println!(
+                "  {:<20} {:>12.precision$} {:>12.precision$} {:>12}",
+                label,
+                base,
+                cand,
+                fmt_pct(base, cand, lower)
+            );
+        };
+        let ms = |us: Option<u64>| us.unwrap_or(0) as f64 / 1_000.0;
+        row(
+            "Requests",
+            base.total_requests.unwrap_or(0) as f64,
+            cand.total_requests.unwrap_or(0) as f64,
+            0,
+            false,
+        );
+        row(
+            "Mean (ms)",
+            base.mean_us.unwrap_or(0.0) / 1_000.0,
+            cand.mean_us.unwrap_or(0.0) / 1_000.0,
+            3,
+            true,
+        );
+        row("p50 (ms)", ms(base.p50_us), ms(cand.p50_us), 3, true);
+        row("p95 (ms)", ms(base.p95_us), ms(cand.p95_us), 3, true);
+        row("p99 (ms)", ms(base.p99_us), ms(cand.p99_us), 3, true);
+        row(
+            "Errors",
+            base.errors() as f64,
+            cand.errors() as f64,
+            0,
+            true,
+        );
+    }
+    for cand in &candidate.steps {
+        if !baseline.steps.iter().any(|s| s.name == cand.name) {
+            println!();
+            println!("{} {}", cand.name.cyan(), "(only in candidate)".dimmed());
+        }
+    }
 
     println!("{}", "═".repeat(72).dimmed());
 }
@@ -416,24 +1541,24 @@ pub fn print_comparison(baseline: &RunSnapshot, candidate: &RunSnapshot) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use goku_core::benchmark::{BenchmarkResult, Report};
+    use goku_core::benchmark::{BenchmarkResult, Phases, Report, TransactionOutcome};
+
+    fn make_results() -> Vec<BenchmarkResult> {
+        [(10_000, 200), (20_000, 200), (50_000, 200), (100_000, 500)]
+            .into_iter()
+            .map(|(d, code)| BenchmarkResult {
+                outcome: Outcome::Status(code),
+                duration_us: d,
+                ..BenchmarkResult::default()
+            })
+            .collect()
+    }
 
     fn make_report() -> Report {
         let mut r = Report::new(2);
-        for (d, s) in [
-            (10, "200 OK"),
-            (20, "200 OK"),
-            (50, "200 OK"),
-            (100, "500 Internal Server Error"),
-        ] {
-            r.add_result(BenchmarkResult {
-                status: s.to_string(),
-                duration: d,
-                num_client: 0,
-                execution: 0,
-                timestamp_ms: 0,
-            });
-        }
+        make_results()
+            .into_iter()
+            .for_each(|result| r.add_result(result));
         r
     }
 
@@ -456,6 +1581,74 @@ mod tests {
         assert!(v.get("p95_ms").is_some());
         assert!(v.get("status_5xx").is_some());
         assert!(v.get("p99_ms").is_some());
+        assert!(v.get("p99_us").is_some());
+        assert_eq!(v["min_ms"], 10);
+        assert_eq!(v["min_us"], 10_000);
+        assert_eq!(v["bytes_received"], 0);
+        assert!(v.get("received_mb_per_sec").is_some());
+    }
+
+    #[test]
+    fn json_output_includes_corrected_percentiles_when_enabled() {
+        let mut r = Report::new(1).with_corrected_histogram();
+        r.add_result(BenchmarkResult {
+            send_delay_us: 40_000,
+            ..make_results().remove(0)
+        });
+        let mut out = Vec::new();
+        print_json(&r, &mut out);
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(v["p99_ms"], 10);
+        assert_eq!(v["corrected_p99_ms"], 50);
+        assert!(v["corrected_p99_us"].as_u64().unwrap() >= 50_000);
+    }
+
+    #[test]
+    fn json_output_corrected_percentiles_are_null_without_rate() {
+        let r = make_report();
+        let mut out = Vec::new();
+        print_json(&r, &mut out);
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert!(v["corrected_p99_ms"].is_null());
+    }
+
+    #[test]
+    fn json_output_has_phase_breakdown() {
+        let mut r = Report::new(1);
+        r.add_result(BenchmarkResult {
+            phases: Phases {
+                connect_us: Some(400),
+                ttfb_us: Some(600),
+                ..Phases::default()
+            },
+            ..make_results().remove(0)
+        });
+        let mut out = Vec::new();
+        print_json(&r, &mut out);
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(v["phases"]["connect"]["count"], 1);
+        assert_eq!(v["phases"]["ttfb"]["count"], 1);
+        assert!(v["phases"].get("dns").is_none());
+    }
+
+    /// Parses the CSV output (rejecting rows whose length differs from the header) into
+    /// column-to-value maps, one per row.
+    fn csv_rows(r: &Report) -> Vec<std::collections::HashMap<String, String>> {
+        let mut out = Vec::new();
+        print_csv(r, &mut out);
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        let header = reader.headers().unwrap().clone();
+        reader
+            .records()
+            .map(|record| {
+                let record = record.unwrap();
+                header
+                    .iter()
+                    .map(String::from)
+                    .zip(record.iter().map(String::from))
+                    .collect()
+            })
+            .collect()
     }
 
     #[test]
@@ -465,18 +1658,129 @@ mod tests {
         print_csv(&r, &mut out);
         let s = String::from_utf8(out).unwrap();
         assert!(s.starts_with("concurrency,"));
-        let lines: Vec<&str> = s.trim().lines().collect();
-        assert_eq!(lines.len(), 2); // header + data row
+        let rows = csv_rows(&r);
+        // The run, then one row per status code.
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0]["scope"], "run");
+        assert_eq!(rows[0]["total_requests"], "4");
+        assert_eq!(rows[1]["scope"], "status_code");
+        assert_eq!(rows[1]["concurrency"], "");
     }
 
     #[test]
-    fn results_log_has_header_and_data() {
-        let r = make_report();
+    fn transactions_appear_in_json_and_csv() {
+        let mut r = Report::new(1).with_transactions(&["checkout"]);
+        r.add_result(BenchmarkResult {
+            transaction: Some(TransactionOutcome {
+                index: 0,
+                duration_us: 250_000,
+                failed: false,
+            }),
+            ..make_results().pop().unwrap()
+        });
+
         let mut out = Vec::new();
-        write_results_log(&r, &mut out);
-        let s = String::from_utf8(out).unwrap();
+        print_json(&r, &mut out);
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(v["transactions"][0]["name"], "checkout");
+        assert_eq!(v["transactions"][0]["count"], 1);
+        assert!(v["transactions"][0]["p99_us"].as_u64().unwrap() >= 250_000);
+
+        let rows = csv_rows(&r);
+        let row = rows
+            .iter()
+            .find(|row| row["scope"] == "transaction")
+            .unwrap();
+        assert_eq!(row["name"], "checkout");
+        assert_eq!(row["count"], "1");
+        assert_eq!(row["total_requests"], "");
+        assert_eq!(row["failed"], "0");
+        assert!(row["p99_us"].parse::<u64>().unwrap() >= 250_000);
+    }
+
+    #[test]
+    fn steps_appear_in_json_csv_and_snapshot() {
+        let step: goku_core::settings::Step =
+            serde_json::from_str(r#"{"target": "GET http://localhost/search?q=a,b"}"#).unwrap();
+        let mut r = Report::new(1).with_steps(&[(step.metrics_name(), &step)]);
+        for result in make_results() {
+            r.add_result(BenchmarkResult {
+                step: Some(0),
+                ..result
+            });
+        }
+
+        let mut out = Vec::new();
+        print_json(&r, &mut out);
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(v["steps"][0]["name"], "GET http://localhost/search?q=a,b");
+        assert_eq!(v["steps"][0]["total_requests"], 4);
+        assert_eq!(v["steps"][0]["status_5xx"], 1);
+        let snapshot: RunSnapshot = serde_json::from_slice(&out).unwrap();
+        assert_eq!(snapshot.steps[0].name, "GET http://localhost/search?q=a,b");
+        assert_eq!(snapshot.steps[0].errors(), 1);
+
+        let rows = csv_rows(&r);
+        let row = rows.iter().find(|row| row["scope"] == "step").unwrap();
+        assert_eq!(row["name"], "GET http://localhost/search?q=a,b");
+        assert_eq!(row["target"], "GET http://localhost/search?q=a,b");
+        assert_eq!(row["total_requests"], "4");
+        assert_eq!(row["status_5xx"], "1");
+    }
+
+    #[test]
+    fn outputs_count_exact_status_codes_and_error_kinds() {
+        let mut r = make_report();
+        let result = BenchmarkResult {
+            outcome: Outcome::Error {
+                kind: ErrorKind::Timeout,
+                message: "operation timed out".to_string(),
+            },
+            ..make_results().remove(0)
+        };
+        r.add_result(result.clone());
+
+        let mut out = Vec::new();
+        print_json(&r, &mut out);
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(v["status_codes"], serde_json::json!({"200": 3, "500": 1}));
+        assert_eq!(v["errors"]["timeout"]["count"], 1);
+        assert_eq!(v["errors"]["timeout"]["samples"][0], "operation timed out");
+
+        let rows = csv_rows(&r);
+        assert_eq!(rows[0]["timeout_errors"], "1");
+        assert_eq!(rows[0]["dns_errors"], "0");
+        let codes: Vec<(&str, &str)> = rows[1..]
+            .iter()
+            .map(|row| (row["name"].as_str(), row["total_requests"].as_str()))
+            .collect();
+        assert_eq!(codes, vec![("200", "3"), ("500", "1")]);
+
+        let mut log = ResultsLog::new(Vec::new()).unwrap();
+        log.write(&result, None).unwrap();
+        let s = String::from_utf8(log.finish().unwrap()).unwrap();
+        let last = s.trim().lines().last().unwrap();
+        assert!(last.contains(",timeout,"));
+        assert!(last.ends_with(",operation timed out"));
+    }
+
+    #[test]
+    fn results_log_has_header_and_data() {
+        let mut log = ResultsLog::new(Vec::new()).unwrap();
+        for result in make_results() {
+            log.write(&result, None).unwrap();
+        }
+        log.write(
+            &BenchmarkResult {
+                outcome: Outcome::Dropped,
+                ..make_results().remove(0)
+            },
+            None,
+        )
+        .unwrap();
+        let s = String::from_utf8(log.finish().unwrap()).unwrap();
         assert!(s.starts_with("timestamp_ms,"));
         let lines: Vec<&str> = s.trim().lines().collect();
-        assert_eq!(lines.len(), 5); // header + 4 results
+        assert_eq!(lines.len(), 5); // header + 4 results, without the dropped iteration
     }
 }
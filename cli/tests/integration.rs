@@ -25,7 +25,7 @@ fn test_basic_get_requests() {
         .stdout(predicate::str::is_match(r"Total requests\s+10").unwrap())
         .stdout(predicate::str::is_match(r"2xx.*10").unwrap());
 
-    mock.assert_hits(10);
+    mock.assert_calls(10);
 }
 
 #[test]
@@ -59,7 +59,132 @@ fn test_post_with_body_and_headers() {
         .success()
         .stdout(predicate::str::is_match(r"2xx.*5").unwrap());
 
-    mock.assert_hits(5);
+    mock.assert_calls(5);
+}
+
+#[test]
+fn test_exact_status_codes_and_error_kinds() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/slow");
+        then.status(200)
+            .delay(std::time::Duration::from_millis(500));
+    });
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-c")
+        .arg("1")
+        .arg("-i")
+        .arg("2")
+        .arg("--timeout")
+        .arg("50")
+        .arg("--output")
+        .arg("json")
+        .arg("--target")
+        .arg(server.url("/slow"));
+
+    let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    // The report follows the banner line.
+    let v: serde_json::Value = serde_json::from_str(&output[output.find('{').unwrap()..]).unwrap();
+    assert_eq!(v["network_errors"], 2);
+    assert_eq!(v["errors"]["timeout"]["count"], 2);
+    assert_eq!(
+        v["errors"]["timeout"]["samples"].as_array().unwrap().len(),
+        1
+    );
+    assert_eq!(v["status_codes"], serde_json::json!({}));
+    mock.assert_calls(2);
+
+    let not_found = server.mock(|when, then| {
+        when.method("GET").path("/missing");
+        then.status(404);
+    });
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-c")
+        .arg("1")
+        .arg("-i")
+        .arg("3")
+        .arg("--target")
+        .arg(server.url("/missing"));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"4xx\s+3\n\s+404\s+3").unwrap());
+    not_found.assert_calls(3);
+
+    // Nothing listens on port 1.
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-c")
+        .arg("1")
+        .arg("-i")
+        .arg("2")
+        .arg("--target")
+        .arg("http://127.0.0.1:1/");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"network errors\s+2").unwrap())
+        .stdout(predicate::str::is_match(r"Errors\n\s+connect\s+2\n").unwrap());
+}
+
+#[test]
+fn test_connection_closed_before_response_is_a_reset() {
+    // Reads each request, then closes the connection without answering.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = std::io::Read::read(&mut stream, &mut [0; 1024]);
+        }
+    });
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-c")
+        .arg("1")
+        .arg("-i")
+        .arg("2")
+        .arg("--target")
+        .arg(format!("http://{}/", addr));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Errors\n\s+reset\s+2\n").unwrap());
+}
+
+#[test]
+fn test_invalid_rendered_header_is_an_error() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/api");
+        then.status(200);
+    });
+
+    let mut data = NamedTempFile::with_suffix(".jsonl").unwrap();
+    writeln!(data, r#"{{"token": "abc\ndef"}}"#).unwrap();
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 2
+feeders:
+  - name: users
+    file: "{}"
+steps:
+  - target: "{}"
+    headers:
+      - key: "X-Token"
+        value: "{{{{users.token}}}}"
+"#,
+        data.path().display(),
+        server.url("/api")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Errors\n\s+invalid_header\s+2\n").unwrap())
+        .stdout(predicate::str::contains("Invalid header value for x-token"));
+    mock.assert_calls(0);
 }
 
 #[test]
@@ -84,7 +209,7 @@ fn test_rate_limiting() {
         .arg(server.url("/limit"));
 
     cmd.assert().success();
-    mock.assert_hits(5);
+    mock.assert_calls(5);
 
     let elapsed = start.elapsed();
     assert!(elapsed.as_secs() >= 2);
@@ -110,7 +235,7 @@ fn test_server_errors_5xx() {
         .success()
         .stdout(predicate::str::is_match(r"5xx.*8").unwrap());
 
-    mock.assert_hits(8);
+    mock.assert_calls(8);
 }
 
 #[test]
@@ -132,19 +257,19 @@ fn test_auth_bearer() {
         .arg(server.url("/protected"));
 
     cmd.assert().success();
-    mock.assert_hits(3);
+    mock.assert_calls(3);
 }
 
 #[test]
 fn test_multi_step_scenario() {
     let server = MockServer::start();
-    
+
     // Step 1: GET /step1
     let mock1 = server.mock(|when, then| {
         when.method("GET").path("/step1");
         then.status(200);
     });
-    
+
     // Step 2: POST /step2
     let mock2 = server.mock(|when, then| {
         when.method("POST")
@@ -185,57 +310,1209 @@ steps:
         .stdout(predicate::str::is_match(r"Total requests\s+4").unwrap())
         .stdout(predicate::str::is_match(r"2xx.*4").unwrap());
 
-    mock1.assert_hits(2);
-    mock2.assert_hits(2);
+    mock1.assert_calls(2);
+    mock2.assert_calls(2);
 }
 
 #[test]
-fn test_compare_subcommand() {
-    let base_json = r#"{
-        "requests_per_sec": 100.0,
-        "mean_ms": 50.0,
-        "p50_ms": 40,
-        "p95_ms": 60,
-        "p99_ms": 70,
-        "p999_ms": 80,
-        "min_ms": 10,
-        "max_ms": 90,
-        "total_requests": 1000,
-        "status_2xx": 1000,
-        "status_4xx": 0,
-        "status_5xx": 0,
-        "network_errors": 0
-    }"#;
-    let cand_json = r#"{
-        "requests_per_sec": 120.0,
-        "mean_ms": 40.0,
-        "p50_ms": 35,
-        "p95_ms": 55,
-        "p99_ms": 65,
-        "p999_ms": 75,
-        "min_ms": 8,
-        "max_ms": 85,
-        "total_requests": 1000,
-        "status_2xx": 1000,
-        "status_4xx": 0,
-        "status_5xx": 0,
-        "network_errors": 0
-    }"#;
+fn test_template_functions_render_per_request() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("POST")
+            .path("/search")
+            .query_param("q", "red shoes")
+            .header("Authorization", "Basic dXNlcjpwYXNz")
+            .header(
+                "X-Signature",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            )
+            .body_matches(r#"^\{"size": [1-9], "tag": "[A-Za-z0-9]{8}"\}$"#);
+        then.status(200);
+    });
 
-    let mut base_file = NamedTempFile::new().unwrap();
-    write!(base_file, "{}", base_json).unwrap();
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 5
+steps:
+  - target: "POST {}?q={{{{urlencode('red shoes')}}}}"
+    body: '{{"size": {{{{random_int(1, 9)}}}}, "tag": "{{{{random_string(8)}}}}"}}'
+    headers:
+      - key: "Authorization"
+        value: "Basic {{{{base64('user:pass')}}}}"
+      - key: "X-Signature"
+        value: "{{{{sha256(\"abc\")}}}}"
+"#,
+        server.url("/search")
+    );
 
-    let mut cand_file = NamedTempFile::new().unwrap();
-    write!(cand_file, "{}", cand_json).unwrap();
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
 
     let mut cmd = Command::cargo_bin("goku").unwrap();
-    cmd.arg("compare")
-        .arg(base_file.path())
-        .arg(cand_file.path());
+    cmd.arg("--scenario").arg(file.path());
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("Benchmark Comparison"))
-        .stdout(predicate::str::contains("Requests/sec"))
-        .stdout(predicate::str::contains("+20.0%"));
+        .stdout(predicate::str::is_match(r"2xx\s+5").unwrap());
+    mock.assert_calls(5);
+}
+
+#[test]
+fn test_form_and_multipart_bodies() {
+    let server = MockServer::start();
+    let form = server.mock(|when, then| {
+        when.method("POST")
+            .path("/login")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("user=ana+%26+bo&remember=true");
+        then.status(200);
+    });
+    let upload = server.mock(|when, then| {
+        when.method("POST")
+            .path("/upload")
+            .header_includes("Content-Type", "multipart/form-data; boundary=")
+            .body_includes("Content-Disposition: form-data; name=\"title\"\r\n\r\nholiday\r\n")
+            .body_includes("name=\"photo\"; filename=\"photo.png\"\r\nContent-Type: image/png")
+            .body_includes("\u{1}\u{2}PNG-bytes");
+        then.status(200);
+    });
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-i")
+        .arg("2")
+        .arg("--target")
+        .arg(format!("POST {}", server.url("/login")))
+        .arg("--form")
+        .arg("user=ana & bo")
+        .arg("--form")
+        .arg("remember=true");
+    cmd.assert().success();
+    form.assert_calls(2);
+
+    let dir = tempfile::tempdir().unwrap();
+    let photo = dir.path().join("photo.png");
+    std::fs::write(&photo, b"\x01\x02PNG-bytes").unwrap();
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 2
+steps:
+  - target: "POST {}"
+    multipart:
+      - name: title
+        value: holiday
+      - name: photo
+        file: "{}"
+        content_type: image/png
+"#,
+        server.url("/upload"),
+        photo.display()
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+2").unwrap());
+    upload.assert_calls(2);
+}
+
+#[test]
+fn test_structured_json_body() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("POST")
+            .path("/orders")
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"customer":"O'Brien \"Bob\" 3","items":[{"sku":"A-1","qty":2}],"gift":false}"#,
+            );
+        then.status(201);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 3
+seed: 1
+steps:
+  - target: "POST {}"
+    body:
+      customer: "O'Brien \"Bob\" {{{{random_choice(3)}}}}"
+      items:
+        - sku: A-1
+          qty: 2
+      gift: false
+"#,
+        server.url("/orders")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+3").unwrap());
+    mock.assert_calls(3);
+}
+
+#[test]
+fn test_options_and_custom_methods() {
+    let server = MockServer::start();
+    let options = server.mock(|when, then| {
+        when.method("OPTIONS").path("/items");
+        then.status(204);
+    });
+    let purge = server.mock(|when, then| {
+        // httpmock's `method` only knows the standard methods.
+        when.path("/cache")
+            .is_true(|req| req.method_str() == "PURGE");
+        then.status(200);
+    });
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-i")
+        .arg("2")
+        .arg("--target")
+        .arg(format!("options {}", server.url("/items")));
+    cmd.assert().success();
+    options.assert_calls(2);
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-i")
+        .arg("2")
+        .arg("--target")
+        .arg(format!("PURGE {}", server.url("/cache")));
+    cmd.assert().success();
+    purge.assert_calls(2);
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--target")
+        .arg(format!("GE(T {}", server.url("/cache")));
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid HTTP method 'GE(T'"));
+}
+
+#[test]
+fn test_step_overrides_timeout_and_auth() {
+    let server = MockServer::start();
+    let report = server.mock(|when, then| {
+        when.method("GET")
+            .path("/report")
+            .header("Authorization", "Bearer report-token");
+        then.status(200)
+            .delay(std::time::Duration::from_millis(300));
+    });
+    let api = server.mock(|when, then| {
+        when.method("GET")
+            .path("/api")
+            .header("Authorization", "Basic dXNlcjpwYXNz");
+        then.status(200)
+            .delay(std::time::Duration::from_millis(300));
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+auth:
+  type: bearer
+  token: report-token
+steps:
+  - target: "{}"
+    timeout_ms: 2000
+  - target: "{}"
+    timeout_ms: 50
+    auth:
+      type: basic
+      user: user
+      password: pass
+"#,
+        server.url("/report"),
+        server.url("/api")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+2").unwrap())
+        .stdout(predicate::str::is_match(r"network errors\s+2").unwrap());
+    report.assert_calls(2);
+    api.assert_calls(2);
+}
+
+#[test]
+fn test_setup_values_reach_workers_and_teardown_runs_once() {
+    let server = MockServer::start();
+    let create = server.mock(|when, then| {
+        when.method("POST").path("/tenants");
+        then.status(201)
+            .header("content-type", "application/json")
+            .body(r#"{"id":"t-42"}"#);
+    });
+    let load = server.mock(|when, then| {
+        when.method("GET").path("/tenants/t-42/items");
+        then.status(200);
+    });
+    let delete = server.mock(|when, then| {
+        when.method("DELETE").path("/tenants/t-42");
+        then.status(204);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 2
+requests: 6
+setup:
+  - target: "POST {}"
+    checks:
+      - status: [201]
+    extract:
+      - name: tenant
+        json_path: "$.id"
+target: "{}"
+teardown:
+  - target: "DELETE {}"
+"#,
+        server.url("/tenants"),
+        server.url("/tenants/{{tenant}}/items"),
+        server.url("/tenants/{{tenant}}")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Total requests\s+6").unwrap())
+        .stdout(predicate::str::is_match(r"2xx\s+6").unwrap());
+    create.assert_calls(1);
+    load.assert_calls(6);
+    delete.assert_calls(1);
+}
+
+#[test]
+fn test_failed_teardown_is_reported_and_later_steps_still_run() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method("GET").path("/api");
+        then.status(200);
+    });
+    server.mock(|when, then| {
+        when.method("DELETE").path("/a");
+        then.status(500);
+    });
+    let cleanup = server.mock(|when, then| {
+        when.method("DELETE").path("/b");
+        then.status(204);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 2
+target: "{}"
+teardown:
+  - target: "DELETE {}"
+  - target: "DELETE {}"
+"#,
+        server.url("/api"),
+        server.url("/a"),
+        server.url("/b")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Total requests\s+2").unwrap())
+        .stderr(predicate::str::contains(
+            "Warning: teardown failed: teardown step 1",
+        ));
+    cleanup.assert_calls(1);
+}
+
+#[test]
+fn test_failed_setup_aborts_before_load() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method("POST").path("/tenants");
+        then.status(500);
+    });
+    let load = server.mock(|when, then| {
+        when.method("GET").path("/api");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 5
+setup:
+  - target: "POST {}"
+target: "{}"
+"#,
+        server.url("/tenants"),
+        server.url("/api")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Setup failed: setup step 1 got '500",
+    ));
+    load.assert_calls(0);
+}
+
+#[test]
+fn test_transactions_time_consecutive_steps() {
+    let server = MockServer::start();
+    let cart = server.mock(|when, then| {
+        when.method("GET").path("/cart");
+        then.status(200).delay(std::time::Duration::from_millis(20));
+    });
+    let order = server.mock(|when, then| {
+        when.method("POST").path("/orders");
+        then.status(201).delay(std::time::Duration::from_millis(20));
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+steps:
+  - target: "{}"
+    transaction: checkout
+    think_time: {{ fixed_ms: 50 }}
+  - target: "POST {}"
+    transaction: checkout
+"#,
+        server.url("/cart"),
+        server.url("/orders")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    // name, count, mean, p50, p95, p99, max, failed
+    let row: Vec<&str> = stdout
+        .lines()
+        .find(|l| l.trim_start().starts_with("checkout"))
+        .expect("transaction row")
+        .split_whitespace()
+        .collect();
+    assert_eq!(row[1], "2");
+    assert_eq!(row[7], "0");
+    // Both requests and the think time between them.
+    let p50_ms: f64 = row[3].trim_end_matches("ms").parse().unwrap();
+    assert!(p50_ms >= 90.0, "{}", stdout);
+    cart.assert_calls(2);
+    order.assert_calls(2);
+}
+
+#[test]
+fn test_transactions_under_arrival_rate() {
+    let server = MockServer::start();
+    let cart = server.mock(|when, then| {
+        when.method("GET").path("/cart");
+        then.status(200);
+    });
+    let order = server.mock(|when, then| {
+        when.method("POST").path("/orders");
+        then.status(201);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+rps: 20
+executor: constant-arrival-rate
+steps:
+  - target: "{}"
+    transaction: checkout
+  - target: "POST {}"
+    transaction: checkout
+"#,
+        server.url("/cart"),
+        server.url("/orders")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    let row: Vec<&str> = stdout
+        .lines()
+        .find(|l| l.trim_start().starts_with("checkout"))
+        .expect("transaction row")
+        .split_whitespace()
+        .collect();
+    assert_eq!(row[1], "2", "{}", stdout);
+    assert!(
+        predicate::str::is_match(r"Iterations \(think time included\)\n\s+count\s+2 ")
+            .unwrap()
+            .eval(&stdout),
+        "{}",
+        stdout
+    );
+    cart.assert_calls(2);
+    order.assert_calls(2);
+}
+
+#[test]
+fn test_seed_reproduces_generated_values() {
+    // Spread of 24 random paths over four endpoints, for one run with the given seed.
+    let paths_hit = |seed: &str| {
+        let server = MockServer::start();
+        let mocks: Vec<_> = (1..=4)
+            .map(|i| {
+                server.mock(|when, then| {
+                    when.method("GET").path(format!("/item/{i}"));
+                    then.status(200);
+                })
+            })
+            .collect();
+
+        let mut cmd = Command::cargo_bin("goku").unwrap();
+        cmd.arg("--target")
+            .arg(server.url("/item/{{random_int(1,4)}}"))
+            .arg("--clients")
+            .arg("2")
+            .arg("--iterations")
+            .arg("24")
+            .arg("--seed")
+            .arg(seed);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains(format!("seed {seed}")))
+            .stdout(predicate::str::is_match(r"2xx\s+24").unwrap());
+        mocks.iter().map(|m| m.calls()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(paths_hit("1234"), paths_hit("1234"));
+}
+
+#[test]
+fn test_steps_are_reported_separately() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method("GET").path("/health");
+        then.status(200);
+    });
+    server.mock(|when, then| {
+        when.method("GET").path("/search");
+        then.status(503).delay(std::time::Duration::from_millis(30));
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 6
+steps:
+  - target: "{}"
+  - target: "{}"
+"#,
+        server.url("/health"),
+        server.url("/search")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    // requests, mean, p50, p95, p99, 2xx, 4xx, 5xx, other, network, name (method and URL)
+    let row = |path: &str| -> Vec<String> {
+        stdout
+            .lines()
+            .find(|l| l.ends_with(path))
+            .unwrap_or_else(|| panic!("no row for {path} in {stdout}"))
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    };
+    let health = row("/health");
+    let search = row("/search");
+    assert_eq!(health[0], "3");
+    assert_eq!(health[5], "3");
+    assert_eq!(health[10], "GET");
+    assert_eq!(search[0], "3");
+    assert_eq!(search[7], "3");
+    assert!(search[2].ends_with("ms"));
+}
+
+#[test]
+fn test_compare_shows_steps() {
+    let snapshot = |p99_us: u64| {
+        format!(
+            r#"{{
+        "requests_per_sec": 100.0,
+        "total_requests": 100,
+        "steps": [
+            {{ "name": "GET /health", "total_requests": 50, "p99_us": 1000 }},
+            {{ "name": "search", "total_requests": 50, "p99_us": {p99_us} }}
+        ]
+    }}"#
+        )
+    };
+    let mut base_file = NamedTempFile::new().unwrap();
+    write!(base_file, "{}", snapshot(100_000)).unwrap();
+    let mut cand_file = NamedTempFile::new().unwrap();
+    write!(cand_file, "{}", snapshot(150_000)).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("compare")
+        .arg(base_file.path())
+        .arg(cand_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("search"))
+        .stdout(
+            predicate::str::is_match(r"p99 \(ms\)\s+100\.000\s+150\.000\s+\S*\+50\.0%").unwrap(),
+        );
+}
+
+#[test]
+fn test_step_names_in_verbose_output_and_results_log() {
+    let server = MockServer::start();
+    let items = server.mock(|when, then| {
+        when.method("GET").path_includes("/items/");
+        then.status(200);
+    });
+    let order = server.mock(|when, then| {
+        when.method("POST").path("/orders");
+        then.status(201);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+verbose: true
+steps:
+  - target: "{}"
+  - target: "POST {}"
+    name: place-order
+"#,
+        server.url("/items/{{seq}}"),
+        server.url("/orders")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+    let log = NamedTempFile::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario")
+        .arg(file.path())
+        .arg("--results-log")
+        .arg(log.path());
+    let template = format!("GET {}", server.url("/items/{{seq}}"));
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.contains(&format!("{template} 200 OK")), "{stdout}");
+    assert!(stdout.contains("place-order 201 Created"), "{stdout}");
+    // One Steps row per step, whatever `{{seq}}` was replaced with.
+    let requests = |name: &str| {
+        let row = stdout
+            .lines()
+            .find(|l| l.ends_with(&format!("  {name}")))
+            .unwrap_or_else(|| panic!("no row for {name} in {stdout}"));
+        row.split_whitespace().next().unwrap().to_string()
+    };
+    assert_eq!(requests(&template), "2");
+    assert_eq!(requests("place-order"), "2");
+    items.assert_calls(2);
+    order.assert_calls(2);
+
+    let log = std::fs::read_to_string(log.path()).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert!(lines[0].ends_with(",step,error"));
+    assert!(lines[1].ends_with(&format!(",{template},")));
+    assert!(lines[2].ends_with(",place-order,"));
+}
+
+#[test]
+fn test_compare_subcommand() {
+    let base_json = r#"{
+        "requests_per_sec": 100.0,
+        "mean_ms": 50.0,
+        "p50_ms": 40,
+        "p95_ms": 60,
+        "p99_ms": 70,
+        "p999_ms": 80,
+        "min_ms": 10,
+        "max_ms": 90,
+        "total_requests": 1000,
+        "status_2xx": 1000,
+        "status_4xx": 0,
+        "status_5xx": 0,
+        "network_errors": 0
+    }"#;
+    let cand_json = r#"{
+        "requests_per_sec": 120.0,
+        "mean_ms": 40.0,
+        "p50_ms": 35,
+        "p95_ms": 55,
+        "p99_ms": 65,
+        "p999_ms": 75,
+        "min_ms": 8,
+        "max_ms": 85,
+        "total_requests": 1000,
+        "status_2xx": 1000,
+        "status_4xx": 0,
+        "status_5xx": 0,
+        "network_errors": 0
+    }"#;
+
+    let mut base_file = NamedTempFile::new().unwrap();
+    write!(base_file, "{}", base_json).unwrap();
+
+    let mut cand_file = NamedTempFile::new().unwrap();
+    write!(cand_file, "{}", cand_json).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("compare")
+        .arg(base_file.path())
+        .arg(cand_file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Benchmark Comparison"))
+        .stdout(predicate::str::contains("Requests/sec"))
+        .stdout(predicate::str::contains("+20.0%"));
+}
+
+#[test]
+fn test_constant_arrival_rate_executor() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/open");
+        then.status(200);
+    });
+
+    let start = std::time::Instant::now();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    // 10 arrivals at 10 per second are scheduled over ~1 second
+    cmd.arg("-c")
+        .arg("4")
+        .arg("-i")
+        .arg("10")
+        .arg("--rps")
+        .arg("10")
+        .arg("--executor")
+        .arg("constant-arrival-rate")
+        .arg("--target")
+        .arg(server.url("/open"));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Total requests\s+10").unwrap())
+        .stdout(predicate::str::contains("Dropped").not());
+
+    mock.assert_calls(10);
+    assert!(start.elapsed().as_millis() >= 900);
+}
+
+#[test]
+fn test_read_body_reports_bytes() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/payload");
+        then.status(200).body("x".repeat(1_000));
+    });
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("-c")
+        .arg("1")
+        .arg("-i")
+        .arg("5")
+        .arg("--read-body")
+        .arg("--output")
+        .arg("json")
+        .arg("--target")
+        .arg(server.url("/payload"));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"bytes_received\": 5000"));
+
+    mock.assert_calls(5);
+}
+
+#[test]
+fn test_checks_count_failed_responses() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/api");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"status":"error"}"#);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+target: "{}"
+clients: 1
+requests: 3
+checks:
+  - status: [200]
+  - header: {{ name: content-type, matches: json }}
+  - json_path: {{ path: "$.status", equals: "ok" }}
+"#,
+        server.url("/api")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+3").unwrap())
+        .stdout(predicate::str::is_match(r"✓ status in \[200\]\s+passed 3\s+failed 0").unwrap())
+        .stdout(predicate::str::is_match(r#"✗ \$\.status == "ok"\s+passed 0\s+failed 3"#).unwrap())
+        .stdout(predicate::str::is_match(r"requests failing checks\s+3").unwrap());
+
+    mock.assert_calls(3);
+}
+
+#[test]
+fn test_extracted_values_feed_later_steps() {
+    let server = MockServer::start();
+    let login = server.mock(|when, then| {
+        when.method("POST").path("/login");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"token":"t0k3n","user":{"id":7}}"#);
+    });
+    let orders = server.mock(|when, then| {
+        when.method("GET")
+            .path("/users/7/orders")
+            .header("Authorization", "Bearer t0k3n");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 2
+steps:
+  - target: "POST {}"
+    extract:
+      - name: token
+        json_path: "$.token"
+      - name: user_id
+        regex: '"id":(\d+)'
+  - target: "{}"
+    headers:
+      - key: "Authorization"
+        value: "Bearer {{{{token}}}}"
+"#,
+        server.url("/login"),
+        server.url("/users/{{user_id}}/orders")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+2").unwrap());
+
+    login.assert_calls(1);
+    orders.assert_calls(1);
+}
+
+#[test]
+fn test_stages_report_metrics_per_stage() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/staged");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 4
+target: "{}"
+stages:
+  - {{ duration: 0, rps: 20 }}
+  - {{ duration: 1, rps: 20 }}
+"#,
+        server.url("/staged")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Stages"))
+        .stdout(predicate::str::contains("2. 1s to 20 rps"));
+
+    // 20 arrivals per second for one second, give or take the last tick.
+    let calls = mock.calls();
+    assert!(
+        (18..=20).contains(&calls),
+        "unexpected request count {calls}"
+    );
+}
+
+#[test]
+fn test_capacity_search_reports_max_sustainable_rate() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/capacity");
+        then.status(200);
+    });
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--target")
+        .arg(server.url("/capacity"))
+        .arg("-c")
+        .arg("4")
+        .arg("capacity")
+        .arg("--start-rps")
+        .arg("10")
+        .arg("--step-rps")
+        .arg("10")
+        .arg("--max-rps")
+        .arg("20")
+        .arg("--probe-duration")
+        .arg("1");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"Max sustainable throughput\s+20 req/s").unwrap())
+        .stderr(predicate::str::contains("[capacity] 10 rps"));
+
+    assert!(mock.calls() >= 25);
+}
+
+#[test]
+fn test_capacity_rejects_setup_and_teardown() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/capacity");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+setup:
+  - target: "{0}"
+target: "{0}"
+"#,
+        server.url("/capacity")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path()).arg("capacity");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not supported by `capacity`"));
+    mock.assert_calls(0);
+}
+
+#[test]
+fn test_think_time_paces_iterations() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/think");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+think_time: {{ fixed_ms: 150 }}
+steps:
+  - target: "{url}"
+  - target: "{url}"
+    think_time: {{ fixed_ms: 50 }}
+"#,
+        url = server.url("/think")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let begin = std::time::Instant::now();
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Iterations (think time included)"))
+        .stdout(predicate::str::is_match(r"count\s+2 ").unwrap());
+
+    // Two iterations of 150 ms + 50 ms of think time.
+    assert!(begin.elapsed() >= std::time::Duration::from_millis(400));
+    mock.assert_calls(4);
+}
+
+#[test]
+fn test_think_time_is_not_send_delay_under_rps() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method("GET").path("/paced");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+rps: 100
+think_time: {{ fixed_ms: 150 }}
+steps:
+  - target: "{}"
+"#,
+        server.url("/paced")
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    let corrected = &stdout[stdout.find("Corrected latency").expect("corrected latency")..];
+    let p99 = corrected
+        .lines()
+        .find_map(|l| l.strip_prefix("p99 "))
+        .expect("corrected p99")
+        .trim();
+    // The 150 ms pauses are not counted as time the requests waited to be sent.
+    assert!(p99.ends_with("µs") || p99.ends_with("ms"), "{}", stdout);
+    if let Some(ms) = p99.strip_suffix("ms") {
+        assert!(ms.parse::<f64>().unwrap() < 100.0, "{}", stdout);
+    }
+    mock.assert_calls(4);
+}
+
+#[test]
+fn test_flows_split_iterations_by_weight() {
+    let server = MockServer::start();
+    let browse = server.mock(|when, then| {
+        when.method("GET").path("/products");
+        then.status(200);
+    });
+    let cart = server.mock(|when, then| {
+        when.method("POST").path("/cart");
+        then.status(200);
+    });
+    let pay = server.mock(|when, then| {
+        when.method("POST").path("/pay");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 2
+requests: 200
+# A fixed seed keeps the weighted draws, and so the counts below, the same on every run.
+seed: 42
+flows:
+  - name: browse
+    weight: 3
+    steps:
+      - target: "{}"
+  - name: buy
+    weight: 1
+    steps:
+      - target: "POST {}"
+      - target: "POST {}"
+"#,
+        server.url("/products"),
+        server.url("/cart"),
+        server.url("/pay")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("2 flows"))
+        .stdout(predicate::str::is_match(r"browse\s+75\.0%").unwrap())
+        .stdout(predicate::str::is_match(r"buy\s+25\.0%").unwrap());
+
+    // Roughly three browse iterations per purchase.
+    assert!(cart.calls() > 0);
+    assert!(browse.calls() > 2 * cart.calls());
+    assert!(pay.calls() <= cart.calls());
+}
+
+#[test]
+fn test_feeder_rows_fill_templates_until_exhausted() {
+    let server = MockServer::start();
+    let users: Vec<_> = ["ana", "bob", "eve"]
+        .iter()
+        .map(|name| {
+            server.mock(|when, then| {
+                when.method("GET")
+                    .path(format!("/users/{name}"))
+                    .header("X-Tier", "gold");
+                then.status(200);
+            })
+        })
+        .collect();
+
+    let mut data = NamedTempFile::with_suffix(".csv").unwrap();
+    write!(data, "name,tier\nana,gold\nbob,gold\neve,gold\n").unwrap();
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 10
+feeders:
+  - name: users
+    file: "{}"
+    on_exhaustion: stop
+steps:
+  - target: "{}"
+    headers:
+      - key: "X-Tier"
+        value: "{{{{users.tier}}}}"
+"#,
+        data.path().display(),
+        server.url("/users/{{users.name}}")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+3").unwrap());
+
+    // Each row is used once, then the feeder stops the test.
+    for mock in users {
+        mock.assert_calls(1);
+    }
+}
+
+#[test]
+fn test_cookie_jar_keeps_session_per_worker() {
+    let server = MockServer::start();
+    let login = server.mock(|when, then| {
+        when.method("POST").path("/login");
+        then.status(200)
+            .header("Set-Cookie", "sid=abc123; Path=/; HttpOnly");
+    });
+    let account = server.mock(|when, then| {
+        when.method("GET")
+            .path("/account")
+            .header("Cookie", "sid=abc123");
+        then.status(200);
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 2
+requests: 4
+cookies: true
+steps:
+  - target: "POST {}"
+  - target: "{}"
+"#,
+        server.url("/login"),
+        server.url("/account")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"2xx\s+4").unwrap());
+
+    login.assert_calls(2);
+    account.assert_calls(2);
+}
+
+#[test]
+fn test_reset_session_clears_cookies_each_iteration() {
+    let server = MockServer::start();
+    let home_with_session = server.mock(|when, then| {
+        when.method("GET").path("/home").header_exists("Cookie");
+        then.status(200);
+    });
+    let home_fresh = server.mock(|when, then| {
+        when.method("GET").path("/home").header_missing("Cookie");
+        then.status(200);
+    });
+    let login = server.mock(|when, then| {
+        when.method("POST").path("/login");
+        then.status(200).header("Set-Cookie", "sid=abc123; Path=/");
+    });
+
+    let scenario_yaml = format!(
+        r#"
+clients: 1
+requests: 4
+cookies: true
+reset_session: true
+steps:
+  - target: "{}"
+  - target: "POST {}"
+"#,
+        server.url("/home"),
+        server.url("/login")
+    );
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", scenario_yaml).unwrap();
+
+    let mut cmd = Command::cargo_bin("goku").unwrap();
+    cmd.arg("--scenario").arg(file.path());
+
+    cmd.assert().success();
+
+    home_fresh.assert_calls(2);
+    home_with_session.assert_calls(0);
+    login.assert_calls(2);
 }
@@ -1,31 +1,41 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
 use hdrhistogram::Histogram;
+use reqwest::StatusCode;
 use tokio::time::Instant;
 
+use crate::settings::{Flow, Stage, Step};
+
 // ---------------------------------------------------------------------------
 // Traits
 // ---------------------------------------------------------------------------
 
+/// Latency aggregates, in microseconds.
 pub trait Metrics {
     fn avg(&self) -> u64;
     fn max(&self) -> u64;
     fn min(&self) -> u64;
 }
 
-impl Metrics for Vec<BenchmarkResult> {
+/// Exact aggregates kept as results arrive; the histogram only keeps 3 significant figures.
+impl Metrics for Report {
     fn avg(&self) -> u64 {
-        if self.is_empty() {
-            return 0;
+        match self.hist.len() {
+            0 => 0,
+            n => self.total_us / n,
         }
-        let total: u64 = self.iter().map(|r| r.duration).sum();
-        total / self.len() as u64
     }
 
     fn max(&self) -> u64 {
-        self.iter().map(|r| r.duration).max().unwrap_or(0)
+        self.max_us
     }
 
     fn min(&self) -> u64 {
-        self.iter().map(|r| r.duration).min().unwrap_or(0)
+        match self.hist.is_empty() {
+            true => 0,
+            false => self.min_us,
+        }
     }
 }
 
@@ -33,59 +43,670 @@ impl Metrics for Vec<BenchmarkResult> {
 // BenchmarkResult
 // ---------------------------------------------------------------------------
 
+/// Time spent in each phase of a request, in microseconds. Connection phases are `None` when the
+/// request reused a pooled connection; `body_us` is `None` when the body was not read.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Phases {
+    /// DNS resolution.
+    pub dns_us: Option<u64>,
+    /// TCP connect.
+    pub connect_us: Option<u64>,
+    /// TLS handshake.
+    pub tls_us: Option<u64>,
+    /// From the request being sent on a ready connection to the response headers.
+    pub ttfb_us: Option<u64>,
+    /// Response body transfer.
+    pub body_us: Option<u64>,
+}
+
+impl Phases {
+    /// Phase names, in the order used by [`Phases::values`] and by reports.
+    pub const NAMES: [&'static str; 5] = ["dns", "connect", "tls", "ttfb", "body"];
+
+    pub fn values(&self) -> [Option<u64>; 5] {
+        [
+            self.dns_us,
+            self.connect_us,
+            self.tls_us,
+            self.ttfb_us,
+            self.body_us,
+        ]
+    }
+}
+
+/// Result of one check on one response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckOutcome {
+    pub name: Arc<str>,
+    pub passed: bool,
+}
+
+/// A completed scenario transaction, reported on the result of its last step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransactionOutcome {
+    /// Index of the transaction in [`Report::transactions`].
+    pub index: usize,
+    /// From the start of the first request to the end of the last step's think time (µs).
+    pub duration_us: u64,
+    /// Whether any of its requests failed or failed a check.
+    pub failed: bool,
+}
+
+/// Why a request got no usable response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    /// The request or one of its phases ran past its timeout.
+    Timeout,
+    /// The host name could not be resolved.
+    Dns,
+    /// The TCP connection could not be established (refused, unreachable…).
+    Connect,
+    /// The TLS handshake failed (certificate, protocol…).
+    Tls,
+    /// The connection was reset or closed before the response was complete.
+    Reset,
+    /// The response body could not be read.
+    Body,
+    /// Redirects could not be followed (too many, or an invalid location).
+    Redirect,
+    /// The URL was invalid after template substitution.
+    InvalidUrl,
+    /// A header value was invalid after template substitution.
+    InvalidHeader,
+    /// Any other failure.
+    Other,
+}
+
+impl ErrorKind {
+    /// Every kind, in report order.
+    pub const ALL: [ErrorKind; 10] = [
+        ErrorKind::Timeout,
+        ErrorKind::Dns,
+        ErrorKind::Connect,
+        ErrorKind::Tls,
+        ErrorKind::Reset,
+        ErrorKind::Body,
+        ErrorKind::Redirect,
+        ErrorKind::InvalidUrl,
+        ErrorKind::InvalidHeader,
+        ErrorKind::Other,
+    ];
+
+    /// Name used in reports, e.g. `timeout` or `invalid_url`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Dns => "dns",
+            ErrorKind::Connect => "connect",
+            ErrorKind::Tls => "tls",
+            ErrorKind::Reset => "reset",
+            ErrorKind::Body => "body",
+            ErrorKind::Redirect => "redirect",
+            ErrorKind::InvalidUrl => "invalid_url",
+            ErrorKind::InvalidHeader => "invalid_header",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What a request produced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// A response with this HTTP status code.
+    Status(u16),
+    /// No usable response.
+    Error { kind: ErrorKind, message: String },
+    /// An iteration the open-model executor could not start on schedule because every
+    /// in-flight slot was busy. Such results carry no latency and are only counted.
+    Dropped,
+}
+
+impl std::fmt::Display for Outcome {
+    /// `200 OK`, `timeout error: <message>` or `Dropped`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Status(code) => {
+                match StatusCode::from_u16(*code)
+                    .ok()
+                    .and_then(|s| s.canonical_reason())
+                {
+                    Some(reason) => write!(f, "{} {}", code, reason),
+                    None => write!(f, "{}", code),
+                }
+            }
+            Outcome::Error { kind, message } => write!(f, "{} error: {}", kind, message),
+            Outcome::Dropped => f.write_str("Dropped"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BenchmarkResult {
-    pub status: String,
-    pub duration: u64,
+    pub outcome: Outcome,
+    /// Request latency in microseconds: until the response headers arrived, or until the last
+    /// body byte when response bodies are read (time to last byte).
+    pub duration_us: u64,
+    /// Time (µs) between the intended send time of a rate-limited request and the moment it
+    /// was actually sent. Always 0 when no rate is configured.
+    pub send_delay_us: u64,
     /// Which worker produced this result.
     pub num_client: usize,
     /// Sequence number within the worker.
     pub execution: u32,
     /// Unix timestamp (ms) when the request started.
     pub timestamp_ms: u64,
+    /// Per-phase breakdown of `duration_us`.
+    pub phases: Phases,
+    /// Response body size in bytes. Only known when response bodies are read.
+    pub bytes_received: u64,
+    /// Request body size in bytes.
+    pub bytes_sent: u64,
+    /// Outcome of each check configured for the request. Empty when the request got no response.
+    pub checks: Vec<CheckOutcome>,
+    /// Index of the load stage the request started in, when running a staged profile.
+    pub stage: Option<usize>,
+    /// Index of the scenario flow the request belongs to, when the scenario defines `flows`.
+    pub flow: Option<usize>,
+    /// Index in [`Report::steps`] of the metrics of the scenario step that sent the request.
+    /// `None` for a single target.
+    pub step: Option<usize>,
+    /// Think time the worker paused for after this request (µs). Not part of `duration_us`.
+    pub think_time_us: u64,
+    /// Duration (µs) of the scenario iteration this request completed, think times included.
+    /// Only set on the last step of an iteration, for scenarios with several steps or think time.
+    pub iteration_us: Option<u64>,
+    /// The transaction this request completed, if it is the last step of one.
+    pub transaction: Option<TransactionOutcome>,
+}
+
+/// A `200` response with every measurement at zero, to build results from with struct update.
+impl Default for BenchmarkResult {
+    fn default() -> Self {
+        BenchmarkResult {
+            outcome: Outcome::Status(200),
+            duration_us: 0,
+            send_delay_us: 0,
+            num_client: 0,
+            execution: 0,
+            timestamp_ms: 0,
+            phases: Phases::default(),
+            bytes_received: 0,
+            bytes_sent: 0,
+            checks: vec![],
+            stage: None,
+            flow: None,
+            step: None,
+            think_time_us: 0,
+            iteration_us: None,
+            transaction: None,
+        }
+    }
+}
+
+impl BenchmarkResult {
+    /// Whether any check on this response failed.
+    pub fn failed_checks(&self) -> bool {
+        self.checks.iter().any(|c| !c.passed)
+    }
+
+    /// First digit of the HTTP status, or `None` when the request got no response.
+    fn status_class(&self) -> Option<u16> {
+        match self.outcome {
+            Outcome::Status(code) => Some(code / 100),
+            _ => None,
+        }
+    }
+
+    /// Whether the request failed: a 4xx or 5xx response, or no response at all.
+    pub fn is_error(&self) -> bool {
+        !matches!(self.status_class(), Some(1..=3))
+    }
+}
+
+/// Formats a latency given in microseconds with the most readable unit
+/// (`850µs`, `12.34ms`, `1.50s`).
+pub fn format_latency(us: f64) -> String {
+    if us < 1_000.0 {
+        format!("{:.0}µs", us)
+    } else if us < 1_000_000.0 {
+        format!("{:.2}ms", us / 1_000.0)
+    } else {
+        format!("{:.2}s", us / 1_000_000.0)
+    }
+}
+
+/// Formats a byte count with a decimal unit (`512 B`, `1.50 kB`, `12.34 MB`, `1.02 GB`).
+pub fn format_bytes(bytes: u64) -> String {
+    let b = bytes as f64;
+    if b < 1_000.0 {
+        format!("{} B", bytes)
+    } else if b < 1_000_000.0 {
+        format!("{:.2} kB", b / 1_000.0)
+    } else if b < 1_000_000_000.0 {
+        format!("{:.2} MB", b / 1_000_000.0)
+    } else {
+        format!("{:.2} GB", b / 1_000_000_000.0)
+    }
 }
 
 // ---------------------------------------------------------------------------
 // StatusBreakdown
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct StatusBreakdown {
-    pub success: usize,      // 2xx
-    pub client_error: usize, // 4xx
-    pub server_error: usize, // 5xx
+    pub success: usize,       // 2xx
+    pub client_error: usize,  // 4xx
+    pub server_error: usize,  // 5xx
     pub network_error: usize, // timeouts, connection refused, etc.
-    pub other: usize,        // 1xx, 3xx or any other
+    pub other: usize,         // 1xx, 3xx or any other
+}
+
+impl StatusBreakdown {
+    fn record(&mut self, result: &BenchmarkResult) {
+        match result.status_class() {
+            Some(2) => self.success += 1,
+            Some(4) => self.client_error += 1,
+            Some(5) => self.server_error += 1,
+            Some(_) => self.other += 1,
+            None => self.network_error += 1,
+        }
+    }
+
+    /// Requests that got a 4xx/5xx response or no response.
+    pub fn errors(&self) -> usize {
+        self.client_error + self.server_error + self.network_error
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ErrorStats
+// ---------------------------------------------------------------------------
+
+/// Distinct messages kept per error kind as samples.
+pub const MAX_ERROR_SAMPLES: usize = 3;
+
+/// Failures of one [`ErrorKind`] across the run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorStats {
+    pub count: u64,
+    /// The first distinct messages, at most [`MAX_ERROR_SAMPLES`].
+    pub samples: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// CheckStats
+// ---------------------------------------------------------------------------
+
+/// Pass/fail counts of one check across the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckStats {
+    pub name: String,
+    pub passed: u64,
+    pub failed: u64,
+}
+
+// ---------------------------------------------------------------------------
+// StageStats
+// ---------------------------------------------------------------------------
+
+/// Metrics of one stage of a staged load profile.
+#[derive(Debug)]
+pub struct StageStats {
+    /// Stage description, e.g. `30s to 50 clients`.
+    pub name: String,
+    /// Planned stage length in seconds.
+    pub duration_secs: u64,
+    /// Latency of the requests started during the stage.
+    pub hist: Histogram<u64>,
+    /// Requests that got a 4xx/5xx response or no response.
+    pub errors: u64,
+    /// Requests that failed at least one check.
+    pub check_failures: u64,
+    /// Iterations dropped by the arrival-rate generator.
+    pub dropped: u64,
+}
+
+impl StageStats {
+    fn new(stage: &Stage) -> Self {
+        StageStats {
+            name: stage.to_string(),
+            duration_secs: stage.duration,
+            hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            errors: 0,
+            check_failures: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        if self.duration_secs > 0 {
+            self.hist.len() as f64 / self.duration_secs as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FlowStats
+// ---------------------------------------------------------------------------
+
+/// Metrics of one flow of a weighted scenario.
+#[derive(Debug)]
+pub struct FlowStats {
+    pub name: String,
+    pub weight: u32,
+    /// Latency of the flow's requests.
+    pub hist: Histogram<u64>,
+    /// Durations of the flow's completed iterations, think time included.
+    pub iteration_hist: Histogram<u64>,
+    /// Requests that got a 4xx/5xx response or no response.
+    pub errors: u64,
+    /// Requests that failed at least one check.
+    pub check_failures: u64,
+}
+
+impl FlowStats {
+    fn new(flow: &Flow) -> Self {
+        FlowStats {
+            name: flow.name.clone(),
+            weight: flow.weight,
+            hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            iteration_hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            errors: 0,
+            check_failures: 0,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// StepStats
+// ---------------------------------------------------------------------------
+
+/// Metrics of one scenario step, or of the steps sharing a name, so a slow endpoint does not
+/// hide behind fast ones.
+#[derive(Debug)]
+pub struct StepStats {
+    /// The step's `name`, or its method and URL template (see `Step::metrics_name`).
+    pub name: String,
+    /// The `target` of the (first) step, as configured.
+    pub target: String,
+    /// Latency of the step's requests.
+    pub hist: Histogram<u64>,
+    pub statuses: StatusBreakdown,
+    /// Requests that failed at least one check.
+    pub check_failures: u64,
+}
+
+impl StepStats {
+    fn new(name: String, step: &Step) -> Self {
+        StepStats {
+            name,
+            target: step.target.trim().to_string(),
+            hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            statuses: StatusBreakdown::default(),
+            check_failures: 0,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TransactionStats
+// ---------------------------------------------------------------------------
+
+/// Durations of one named transaction: a group of consecutive scenario steps timed together.
+#[derive(Debug)]
+pub struct TransactionStats {
+    pub name: String,
+    /// Durations of the completed transactions, think time included.
+    pub hist: Histogram<u64>,
+    /// Transactions with at least one failed request or check.
+    pub failed: u64,
+}
+
+impl TransactionStats {
+    fn new(name: &str) -> Self {
+        TransactionStats {
+            name: name.to_string(),
+            hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            failed: 0,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Report
 // ---------------------------------------------------------------------------
 
+/// Metrics of a run, aggregated as results arrive so memory does not grow with its length.
 #[derive(Debug)]
 pub struct Report {
     pub clients: u32,
-    pub results: Vec<BenchmarkResult>,
     pub hist: Histogram<u64>,
+    /// Exact latency aggregates behind [`Metrics`].
+    min_us: u64,
+    max_us: u64,
+    total_us: u64,
+    /// Latency measured from the intended send time (coordinated-omission corrected).
+    /// Only present when requests follow a rate schedule.
+    pub corrected_hist: Option<Histogram<u64>>,
     pub start: Instant,
+    /// Iterations skipped because the generator could not keep the arrival schedule.
+    pub dropped: u64,
+    /// One histogram per entry of [`Phases::NAMES`]; only phases that happened are recorded.
+    pub phase_hists: [Histogram<u64>; 5],
+    /// Total response body bytes read.
+    pub bytes_received: u64,
+    /// Total request body bytes sent.
+    pub bytes_sent: u64,
+    /// Per-check counts, in the order checks were first seen.
+    pub checks: Vec<CheckStats>,
+    /// Requests that got a response but failed at least one check.
+    pub check_failures: u64,
+    /// Per-stage metrics, one entry per configured stage. Empty without a staged profile.
+    pub stages: Vec<StageStats>,
+    /// Durations of completed scenario iterations, think time included. Empty unless the
+    /// scenario has several steps or think time.
+    pub iteration_hist: Histogram<u64>,
+    /// Per-flow metrics, one entry per configured flow. Empty without `flows`.
+    pub flows: Vec<FlowStats>,
+    /// Per-transaction durations, one entry per transaction name. Empty without transactions.
+    pub transactions: Vec<TransactionStats>,
+    /// Per-step metrics, one entry per scenario step across flows. Empty for a single target.
+    pub steps: Vec<StepStats>,
+    /// Responses per exact HTTP status code.
+    pub status_codes: BTreeMap<u16, u64>,
+    /// Requests without a usable response, per kind of failure.
+    pub errors: BTreeMap<ErrorKind, ErrorStats>,
+    /// Requests per status class.
+    statuses: StatusBreakdown,
+    /// Requests that failed (see [`BenchmarkResult::is_error`]) or failed a check.
+    pub failed_requests: u64,
 }
 
 impl Report {
     pub fn new(clients: u32) -> Self {
         Report {
             clients,
-            results: vec![],
             // sigfig = 3 gives ~0.1% precision, plenty for latency histograms
             hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            min_us: u64::MAX,
+            max_us: 0,
+            total_us: 0,
+            corrected_hist: None,
             start: Instant::now(),
+            dropped: 0,
+            phase_hists: std::array::from_fn(|_| {
+                Histogram::<u64>::new(3).expect("Failed to create HDR histogram")
+            }),
+            bytes_received: 0,
+            bytes_sent: 0,
+            checks: vec![],
+            check_failures: 0,
+            stages: vec![],
+            iteration_hist: Histogram::<u64>::new(3).expect("Failed to create HDR histogram"),
+            flows: vec![],
+            transactions: vec![],
+            steps: vec![],
+            status_codes: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            statuses: StatusBreakdown::default(),
+            failed_requests: 0,
         }
     }
 
+    /// Also breaks metrics down by load stage.
+    pub fn with_stages(mut self, stages: &[Stage]) -> Self {
+        self.stages = stages.iter().map(StageStats::new).collect();
+        self
+    }
+
+    /// Also breaks metrics down by scenario flow.
+    pub fn with_flows(mut self, flows: &[Flow]) -> Self {
+        self.flows = flows.iter().map(FlowStats::new).collect();
+        self
+    }
+
+    /// Also breaks metrics down by scenario step, as grouped by `Settings::step_metrics`.
+    pub fn with_steps(mut self, steps: &[(String, &Step)]) -> Self {
+        self.steps = steps
+            .iter()
+            .map(|(name, step)| StepStats::new(name.clone(), step))
+            .collect();
+        self
+    }
+
+    /// Name of the step that sent `result`, for scenario steps.
+    pub fn step_name(&self, result: &BenchmarkResult) -> Option<&str> {
+        result
+            .step
+            .and_then(|i| self.steps.get(i))
+            .map(|s| s.name.as_str())
+    }
+
+    /// Also records the durations of the scenario's transactions, in the order of
+    /// `Settings::transactions`.
+    pub fn with_transactions(mut self, names: &[&str]) -> Self {
+        self.transactions = names
+            .iter()
+            .map(|name| TransactionStats::new(name))
+            .collect();
+        self
+    }
+
+    /// Also records latency from each request's intended send time, so requests held back by
+    /// a stalled target are not hidden from the percentiles (coordinated omission).
+    pub fn with_corrected_histogram(mut self) -> Self {
+        self.corrected_hist =
+            Some(Histogram::<u64>::new(3).expect("Failed to create HDR histogram"));
+        self
+    }
+
     pub fn add_result(&mut self, result: BenchmarkResult) {
-        let duration = result.duration;
-        self.results.push(result);
+        let stage = result.stage.and_then(|i| self.stages.get_mut(i));
+        match &result.outcome {
+            Outcome::Dropped => {
+                self.dropped += 1;
+                if let Some(stage) = stage {
+                    stage.dropped += 1;
+                }
+                return;
+            }
+            Outcome::Status(code) => *self.status_codes.entry(*code).or_default() += 1,
+            Outcome::Error { kind, message } => {
+                let stats = self.errors.entry(*kind).or_default();
+                stats.count += 1;
+                if stats.samples.len() < MAX_ERROR_SAMPLES && !stats.samples.contains(message) {
+                    stats.samples.push(message.clone());
+                }
+            }
+        }
+        if let Some(stage) = stage {
+            let _ = stage.hist.record(result.duration_us);
+            stage.errors += result.is_error() as u64;
+            stage.check_failures += result.failed_checks() as u64;
+        }
+        if let Some(flow) = result.flow.and_then(|i| self.flows.get_mut(i)) {
+            let _ = flow.hist.record(result.duration_us);
+            if let Some(us) = result.iteration_us {
+                let _ = flow.iteration_hist.record(us);
+            }
+            flow.errors += result.is_error() as u64;
+            flow.check_failures += result.failed_checks() as u64;
+        }
+        if let Some(step) = result.step.and_then(|i| self.steps.get_mut(i)) {
+            let _ = step.hist.record(result.duration_us);
+            step.statuses.record(&result);
+            step.check_failures += result.failed_checks() as u64;
+        }
+        if let Some(outcome) = result.transaction {
+            if let Some(stats) = self.transactions.get_mut(outcome.index) {
+                let _ = stats.hist.record(outcome.duration_us);
+                stats.failed += outcome.failed as u64;
+            }
+        }
+        let duration = result.duration_us;
+        let corrected = duration.saturating_add(result.send_delay_us);
+        self.min_us = self.min_us.min(duration);
+        self.max_us = self.max_us.max(duration);
+        self.total_us = self.total_us.saturating_add(duration);
+        self.statuses.record(&result);
+        self.failed_requests += (result.is_error() || result.failed_checks()) as u64;
+        self.bytes_received += result.bytes_received;
+        self.bytes_sent += result.bytes_sent;
+        self.record_checks(&result.checks);
+        if let Some(us) = result.iteration_us {
+            let _ = self.iteration_hist.record(us);
+        }
+        for (hist, value) in self.phase_hists.iter_mut().zip(result.phases.values()) {
+            if let Some(v) = value {
+                let _ = hist.record(v);
+            }
+        }
         // Saturate at u64::MAX rather than panic on out-of-range values.
         let _ = self.hist.record(duration);
+        if let Some(hist) = &mut self.corrected_hist {
+            let _ = hist.record(corrected);
+        }
+    }
+
+    fn record_checks(&mut self, outcomes: &[CheckOutcome]) {
+        for outcome in outcomes {
+            let stats = match self.checks.iter().position(|c| *c.name == *outcome.name) {
+                Some(i) => &mut self.checks[i],
+                None => {
+                    self.checks.push(CheckStats {
+                        name: outcome.name.to_string(),
+                        passed: 0,
+                        failed: 0,
+                    });
+                    self.checks.last_mut().expect("just pushed")
+                }
+            };
+            if outcome.passed {
+                stats.passed += 1;
+            } else {
+                stats.failed += 1;
+            }
+        }
+        if outcomes.iter().any(|c| !c.passed) {
+            self.check_failures += 1;
+        }
+    }
+
+    /// Histograms of the phases that were observed at least once, in [`Phases::NAMES`] order.
+    pub fn recorded_phases(&self) -> impl Iterator<Item = (&'static str, &Histogram<u64>)> {
+        Phases::NAMES
+            .into_iter()
+            .zip(self.phase_hists.iter())
+            .filter(|(_, h)| !h.is_empty())
     }
 
     pub fn requests_per_second(&self) -> f64 {
@@ -97,30 +718,50 @@ impl Report {
         }
     }
 
-    pub fn status_breakdown(&self) -> StatusBreakdown {
-        let mut breakdown = StatusBreakdown::default();
-        for r in &self.results {
-            match r.status.chars().next().and_then(|c| c.to_digit(10)) {
-                Some(2) => breakdown.success += 1,
-                Some(4) => breakdown.client_error += 1,
-                Some(5) => breakdown.server_error += 1,
-                Some(1) | Some(3) => breakdown.other += 1,
-                _ => breakdown.network_error += 1,
-            }
+    /// Completed scenario iterations per second.
+    pub fn iterations_per_second(&self) -> f64 {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.iteration_hist.len() as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Received throughput in MB/s (10^6 bytes per second).
+    pub fn received_mb_per_sec(&self) -> f64 {
+        self.mb_per_sec(self.bytes_received)
+    }
+
+    /// Sent throughput in MB/s (10^6 bytes per second).
+    pub fn sent_mb_per_sec(&self) -> f64 {
+        self.mb_per_sec(self.bytes_sent)
+    }
+
+    fn mb_per_sec(&self, bytes: u64) -> f64 {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            bytes as f64 / 1_000_000.0 / elapsed
+        } else {
+            0.0
         }
-        breakdown
+    }
+
+    pub fn status_breakdown(&self) -> StatusBreakdown {
+        self.statuses.clone()
     }
 
     /// Returns an ASCII-art latency histogram across `n_buckets` buckets.
-    /// Each bucket is labelled with the upper bound (ms) and a bar proportional to count.
+    /// Each bucket is labelled with its upper bound (see [`format_latency`]) and a count.
+    /// Counts come from the HDR histogram, so a value near a bucket bound may land in the
+    /// neighbouring bucket.
     pub fn latency_histogram(&self, n_buckets: usize) -> Vec<(String, u64)> {
-        if self.results.is_empty() || n_buckets == 0 {
+        if self.hist.is_empty() || n_buckets == 0 {
             return vec![];
         }
-        let min = self.results.iter().map(|r| r.duration).min().unwrap_or(0);
-        let max = self.results.iter().map(|r| r.duration).max().unwrap_or(0);
+        let (min, max) = (self.min(), self.max());
         if min == max {
-            return vec![(format!("{}ms", max), self.results.len() as u64)];
+            return vec![(format_latency(max as f64), self.hist.len())];
         }
 
         let range = max - min;
@@ -128,10 +769,14 @@ impl Report {
         let bucket_width = bucket_width.max(1);
 
         let mut counts = vec![0u64; n_buckets];
-        for r in &self.results {
-            let idx = ((r.duration - min) / bucket_width) as usize;
+        for v in self.hist.iter_recorded() {
+            let value = self
+                .hist
+                .median_equivalent(v.value_iterated_to())
+                .clamp(min, max);
+            let idx = ((value - min) / bucket_width) as usize;
             let idx = idx.min(n_buckets - 1);
-            counts[idx] += 1;
+            counts[idx] += v.count_at_value();
         }
 
         counts
@@ -139,7 +784,7 @@ impl Report {
             .enumerate()
             .map(|(i, &count)| {
                 let upper = min + (i as u64 + 1) * bucket_width;
-                (format!("{}ms", upper), count)
+                (format_latency(upper as f64), count)
             })
             .collect()
     }
@@ -153,56 +798,90 @@ impl Report {
 mod tests {
     use super::*;
 
-    fn make_result(duration: u64, status: &str) -> BenchmarkResult {
+    fn make_result(duration_us: u64, outcome: Outcome) -> BenchmarkResult {
         BenchmarkResult {
-            status: status.to_string(),
-            duration,
-            num_client: 0,
-            execution: 0,
-            timestamp_ms: 0,
+            outcome,
+            duration_us,
+            ..BenchmarkResult::default()
         }
     }
 
+    fn connect_error() -> Outcome {
+        Outcome::Error {
+            kind: ErrorKind::Connect,
+            message: "connection refused".to_string(),
+        }
+    }
+
+    fn report_of(durations: &[u64]) -> Report {
+        let mut report = Report::new(1);
+        for d in durations {
+            report.add_result(make_result(*d, Outcome::Status(200)));
+        }
+        report
+    }
+
     // --- Metrics trait ---
 
     #[test]
-    fn avg_empty_vec_returns_zero() {
-        let v: Vec<BenchmarkResult> = vec![];
-        assert_eq!(v.avg(), 0);
+    fn avg_empty_report_returns_zero() {
+        assert_eq!(report_of(&[]).avg(), 0);
     }
 
     #[test]
-    fn min_empty_vec_returns_zero() {
-        let v: Vec<BenchmarkResult> = vec![];
-        assert_eq!(v.min(), 0);
+    fn min_empty_report_returns_zero() {
+        assert_eq!(report_of(&[]).min(), 0);
     }
 
     #[test]
-    fn max_empty_vec_returns_zero() {
-        let v: Vec<BenchmarkResult> = vec![];
-        assert_eq!(v.max(), 0);
+    fn max_empty_report_returns_zero() {
+        assert_eq!(report_of(&[]).max(), 0);
     }
 
     #[test]
     fn avg_correct_value() {
-        let v = vec![
-            make_result(10, "200 OK"),
-            make_result(20, "200 OK"),
-            make_result(30, "200 OK"),
-        ];
-        assert_eq!(v.avg(), 20);
+        assert_eq!(report_of(&[10, 20, 30]).avg(), 20);
     }
 
     #[test]
     fn min_correct_value() {
-        let v = vec![make_result(5, "200 OK"), make_result(15, "200 OK")];
-        assert_eq!(v.min(), 5);
+        assert_eq!(report_of(&[5, 15]).min(), 5);
     }
 
     #[test]
     fn max_correct_value() {
-        let v = vec![make_result(5, "200 OK"), make_result(15, "200 OK")];
-        assert_eq!(v.max(), 15);
+        // Exact, where the histogram would round to 3 significant figures.
+        assert_eq!(report_of(&[5, 123_456_789]).max(), 123_456_789);
+    }
+
+    #[test]
+    fn failed_requests_count_errors_and_failed_checks_once() {
+        let failed_check = || {
+            vec![CheckOutcome {
+                name: Arc::from("status"),
+                passed: false,
+            }]
+        };
+        let mut report = report_of(&[10]);
+        report.add_result(make_result(10, Outcome::Status(500)));
+        report.add_result(BenchmarkResult {
+            checks: failed_check(),
+            ..make_result(10, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            checks: failed_check(),
+            ..make_result(10, Outcome::Status(503))
+        });
+        assert_eq!(report.failed_requests, 3);
+    }
+
+    #[test]
+    fn dropped_iterations_do_not_count_towards_metrics() {
+        let mut report = report_of(&[10]);
+        report.add_result(make_result(0, Outcome::Dropped));
+        assert_eq!(report.min(), 10);
+        assert_eq!(report.avg(), 10);
+        assert_eq!(report.failed_requests, 0);
     }
 
     // --- StatusBreakdown ---
@@ -210,11 +889,11 @@ mod tests {
     #[test]
     fn status_breakdown_counts_correctly() {
         let mut report = Report::new(1);
-        report.add_result(make_result(10, "200 OK"));
-        report.add_result(make_result(10, "201 Created"));
-        report.add_result(make_result(10, "404 Not Found"));
-        report.add_result(make_result(10, "500 Internal Server Error"));
-        report.add_result(make_result(10, "Failed to connect"));
+        report.add_result(make_result(10, Outcome::Status(200)));
+        report.add_result(make_result(10, Outcome::Status(201)));
+        report.add_result(make_result(10, Outcome::Status(404)));
+        report.add_result(make_result(10, Outcome::Status(500)));
+        report.add_result(make_result(10, connect_error()));
 
         let bd = report.status_breakdown();
         assert_eq!(bd.success, 2);
@@ -224,6 +903,46 @@ mod tests {
         assert_eq!(bd.other, 0);
     }
 
+    #[test]
+    fn add_result_counts_status_codes_and_error_kinds() {
+        let mut report = Report::new(1);
+        for outcome in [
+            Outcome::Status(200),
+            Outcome::Status(200),
+            Outcome::Status(503),
+        ] {
+            report.add_result(make_result(10, outcome));
+        }
+        for i in 0..5 {
+            report.add_result(make_result(
+                10,
+                Outcome::Error {
+                    kind: ErrorKind::Timeout,
+                    message: format!("timed out after {}s", i % 4),
+                },
+            ));
+        }
+        report.add_result(make_result(10, connect_error()));
+
+        assert_eq!(report.status_codes, BTreeMap::from([(200, 2), (503, 1)]));
+        let timeouts = &report.errors[&ErrorKind::Timeout];
+        assert_eq!(timeouts.count, 5);
+        assert_eq!(timeouts.samples.len(), MAX_ERROR_SAMPLES);
+        assert_eq!(timeouts.samples[0], "timed out after 0s");
+        assert_eq!(report.errors[&ErrorKind::Connect].count, 1);
+        assert_eq!(report.status_breakdown().network_error, 6);
+    }
+
+    #[test]
+    fn outcome_displays_status_and_error() {
+        assert_eq!(Outcome::Status(404).to_string(), "404 Not Found");
+        assert_eq!(Outcome::Status(599).to_string(), "599");
+        assert_eq!(
+            connect_error().to_string(),
+            "connect error: connection refused"
+        );
+    }
+
     #[test]
     fn status_breakdown_empty_report() {
         let report = Report::new(1);
@@ -237,11 +956,293 @@ mod tests {
     #[test]
     fn add_result_increments_histogram() {
         let mut report = Report::new(2);
-        report.add_result(make_result(100, "200 OK"));
-        report.add_result(make_result(200, "200 OK"));
+        report.add_result(make_result(100, Outcome::Status(200)));
+        report.add_result(make_result(200, Outcome::Status(200)));
         assert_eq!(report.hist.len(), 2);
     }
 
+    #[test]
+    fn add_result_counts_dropped_iterations_apart() {
+        let mut report = Report::new(1);
+        report.add_result(make_result(10, Outcome::Status(200)));
+        report.add_result(make_result(0, Outcome::Dropped));
+        assert_eq!(report.dropped, 1);
+        assert_eq!(report.hist.len(), 1);
+        assert_eq!(report.status_breakdown().network_error, 0);
+    }
+
+    #[test]
+    fn corrected_histogram_is_disabled_by_default() {
+        let mut report = Report::new(1);
+        report.add_result(make_result(10, Outcome::Status(200)));
+        assert!(report.corrected_hist.is_none());
+    }
+
+    #[test]
+    fn corrected_histogram_adds_send_delay() {
+        let mut report = Report::new(1).with_corrected_histogram();
+        report.add_result(BenchmarkResult {
+            send_delay_us: 90,
+            ..make_result(10, Outcome::Status(200))
+        });
+        let corrected = report.corrected_hist.as_ref().unwrap();
+        assert_eq!(report.hist.max(), 10);
+        assert_eq!(corrected.max(), 100);
+    }
+
+    #[test]
+    fn latency_histogram_labels_use_readable_units() {
+        let mut report = Report::new(1);
+        report.add_result(make_result(250, Outcome::Status(200)));
+        report.add_result(make_result(2_250, Outcome::Status(200)));
+        let hist = report.latency_histogram(2);
+        assert_eq!(hist[0].0, "1.25ms");
+        assert_eq!(hist[1].0, "2.25ms");
+    }
+
+    // --- format_latency ---
+
+    #[test]
+    fn format_latency_picks_unit() {
+        assert_eq!(format_latency(850.0), "850µs");
+        assert_eq!(format_latency(12_340.0), "12.34ms");
+        assert_eq!(format_latency(1_500_000.0), "1.50s");
+    }
+
+    #[test]
+    fn format_bytes_picks_unit() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1_500), "1.50 kB");
+        assert_eq!(format_bytes(12_340_000), "12.34 MB");
+    }
+
+    #[test]
+    fn add_result_counts_checks() {
+        let outcome = |name: &str, passed| CheckOutcome {
+            name: name.into(),
+            passed,
+        };
+        let mut report = Report::new(1);
+        report.add_result(BenchmarkResult {
+            checks: vec![outcome("status", true), outcome("body", false)],
+            ..make_result(10, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            checks: vec![outcome("status", true), outcome("body", true)],
+            ..make_result(10, Outcome::Status(200))
+        });
+        assert_eq!(report.check_failures, 1);
+        assert_eq!(
+            report.checks,
+            vec![
+                CheckStats {
+                    name: "status".to_string(),
+                    passed: 2,
+                    failed: 0,
+                },
+                CheckStats {
+                    name: "body".to_string(),
+                    passed: 1,
+                    failed: 1,
+                },
+            ]
+        );
+        // A failing check does not change the HTTP status classification.
+        assert_eq!(report.status_breakdown().success, 2);
+    }
+
+    #[test]
+    fn add_result_breaks_down_by_stage() {
+        let stage = |duration, clients| Stage {
+            duration,
+            clients: Some(clients),
+            rps: None,
+        };
+        let mut report = Report::new(10).with_stages(&[stage(10, 10), stage(5, 0)]);
+        for outcome in [Outcome::Status(200), Outcome::Status(500), connect_error()] {
+            report.add_result(BenchmarkResult {
+                stage: Some(0),
+                ..make_result(10, outcome)
+            });
+        }
+        report.add_result(BenchmarkResult {
+            stage: Some(1),
+            ..make_result(30, Outcome::Status(200))
+        });
+        report.add_result(make_result(50, Outcome::Status(200)));
+
+        assert_eq!(report.hist.len(), 5);
+        assert_eq!(report.stages[0].name, "10s to 10 clients");
+        assert_eq!(report.stages[0].hist.len(), 3);
+        assert_eq!(report.stages[0].errors, 2);
+        assert!((report.stages[0].requests_per_second() - 0.3).abs() < 1e-9);
+        assert_eq!(report.stages[1].hist.len(), 1);
+        assert_eq!(report.stages[1].hist.max(), 30);
+    }
+
+    #[test]
+    fn add_result_breaks_down_by_flow() {
+        let flow = |name: &str, weight| Flow {
+            name: name.to_string(),
+            weight,
+            steps: vec![],
+        };
+        let mut report = Report::new(1).with_flows(&[flow("browse", 3), flow("buy", 1)]);
+        report.add_result(BenchmarkResult {
+            flow: Some(0),
+            iteration_us: Some(100),
+            ..make_result(10, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            flow: Some(1),
+            ..make_result(20, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            flow: Some(1),
+            iteration_us: Some(300),
+            ..make_result(30, Outcome::Status(503))
+        });
+
+        assert_eq!(report.flows[0].name, "browse");
+        assert_eq!(report.flows[0].weight, 3);
+        assert_eq!(report.flows[0].hist.len(), 1);
+        assert_eq!(report.flows[0].iteration_hist.len(), 1);
+        assert_eq!(report.flows[1].hist.len(), 2);
+        assert_eq!(report.flows[1].iteration_hist.max(), 300);
+        assert_eq!(report.flows[1].errors, 1);
+    }
+
+    #[test]
+    fn add_result_breaks_down_by_step() {
+        let step = |target: &str| Step {
+            target: target.to_string(),
+            ..serde_yaml::from_str("target: x").unwrap()
+        };
+        let health = step("GET http://localhost/health");
+        let search = step(" GET http://localhost/search ");
+        let mut report = Report::new(1).with_steps(&[
+            ("health".to_string(), &health),
+            ("GET http://localhost/search".to_string(), &search),
+        ]);
+        report.add_result(BenchmarkResult {
+            step: Some(0),
+            ..make_result(1_000, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            step: Some(1),
+            ..make_result(900_000, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            step: Some(1),
+            ..make_result(50_000, connect_error())
+        });
+
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.steps[0].name, "health");
+        assert_eq!(report.steps[1].target, "GET http://localhost/search");
+        let health = BenchmarkResult {
+            step: Some(0),
+            ..make_result(1_000, Outcome::Status(200))
+        };
+        assert_eq!(report.step_name(&health), Some("health"));
+        assert_eq!(report.steps[0].hist.max(), 1_000);
+        assert_eq!(report.steps[1].hist.len(), 2);
+        assert_eq!(report.steps[1].statuses.success, 1);
+        assert_eq!(report.steps[1].statuses.network_error, 1);
+        assert_eq!(report.steps[1].statuses.errors(), 1);
+    }
+
+    #[test]
+    fn add_result_records_transactions() {
+        let mut report = Report::new(1).with_transactions(&["login", "checkout"]);
+        let done = |index, duration_us, failed| TransactionOutcome {
+            index,
+            duration_us,
+            failed,
+        };
+        report.add_result(make_result(10, Outcome::Status(200)));
+        report.add_result(BenchmarkResult {
+            transaction: Some(done(0, 250, false)),
+            ..make_result(20, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            transaction: Some(done(1, 900, true)),
+            ..make_result(30, Outcome::Status(200))
+        });
+
+        assert_eq!(report.hist.len(), 3);
+        assert_eq!(report.transactions[0].name, "login");
+        assert_eq!(report.transactions[0].hist.len(), 1);
+        assert_eq!(report.transactions[0].hist.max(), 250);
+        assert_eq!(report.transactions[0].failed, 0);
+        assert_eq!(report.transactions[1].hist.len(), 1);
+        assert_eq!(report.transactions[1].failed, 1);
+    }
+
+    #[test]
+    fn add_result_records_iteration_durations() {
+        let mut report = Report::new(1);
+        report.add_result(BenchmarkResult {
+            think_time_us: 1_000_000,
+            ..make_result(10, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            think_time_us: 500_000,
+            iteration_us: Some(1_500_100),
+            ..make_result(20, Outcome::Status(200))
+        });
+        // Think time stays out of request latency.
+        assert_eq!(report.hist.max(), 20);
+        assert_eq!(report.iteration_hist.len(), 1);
+        assert!(report.iteration_hist.max() >= 1_500_000);
+    }
+
+    #[test]
+    fn add_result_sums_transferred_bytes() {
+        let mut report = Report::new(1);
+        report.add_result(BenchmarkResult {
+            bytes_received: 1_000,
+            bytes_sent: 10,
+            ..make_result(10, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            bytes_received: 500,
+            ..make_result(10, Outcome::Status(200))
+        });
+        assert_eq!(report.bytes_received, 1_500);
+        assert_eq!(report.bytes_sent, 10);
+    }
+
+    #[test]
+    fn add_result_records_only_phases_that_happened() {
+        let mut report = Report::new(1);
+        report.add_result(BenchmarkResult {
+            phases: Phases {
+                connect_us: Some(300),
+                ttfb_us: Some(700),
+                ..Phases::default()
+            },
+            ..make_result(1_000, Outcome::Status(200))
+        });
+        report.add_result(BenchmarkResult {
+            phases: Phases {
+                ttfb_us: Some(500),
+                ..Phases::default()
+            },
+            ..make_result(500, Outcome::Status(200))
+        });
+        let lens: Vec<u64> = report.phase_hists.iter().map(|h| h.len()).collect();
+        assert_eq!(lens, vec![0, 1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn latency_histogram_single_value_uses_readable_units() {
+        let mut report = Report::new(1);
+        report.add_result(make_result(1_200, Outcome::Status(200)));
+        report.add_result(make_result(1_200, Outcome::Status(200)));
+        assert_eq!(report.latency_histogram(5), vec![("1.20ms".to_string(), 2)]);
+    }
+
     #[test]
     fn latency_histogram_empty_report() {
         let report = Report::new(1);
@@ -252,7 +1253,7 @@ mod tests {
     fn latency_histogram_produces_correct_bucket_count() {
         let mut report = Report::new(1);
         for d in [10, 20, 30, 40, 50] {
-            report.add_result(make_result(d, "200 OK"));
+            report.add_result(make_result(d, Outcome::Status(200)));
         }
         let hist = report.latency_histogram(5);
         assert_eq!(hist.len(), 5);
@@ -0,0 +1,394 @@
+//! Request bodies: text, JSON, raw bytes read from a file, `application/x-www-form-urlencoded`
+//! forms and `multipart/form-data` forms, compiled once and rendered for every request.
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use rand::distr::Alphanumeric;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+
+use crate::settings::{MultipartField, RequestBody};
+use crate::template::{self, Template};
+
+/// A compiled body. Bodies without templates are built once, as `Static`.
+#[derive(Debug)]
+pub(crate) enum Body {
+    Static(Bytes),
+    Text(Template),
+    Json(JsonTemplate),
+    Form(Vec<(String, Template)>),
+    Multipart { boundary: String, parts: Vec<Part> },
+}
+
+/// A JSON document whose strings are templates. Rendered values are always JSON strings,
+/// escaped as needed.
+#[derive(Debug)]
+pub(crate) enum JsonTemplate {
+    /// A number, boolean or null, as JSON text.
+    Scalar(String),
+    String(Template),
+    Array(Vec<JsonTemplate>),
+    Object(Vec<(String, JsonTemplate)>),
+}
+
+/// One part of a multipart body, with its delimiter and headers ready to send.
+#[derive(Debug)]
+pub(crate) struct Part {
+    head: String,
+    content: PartContent,
+}
+
+#[derive(Debug)]
+enum PartContent {
+    Text(Template),
+    File(Bytes),
+}
+
+impl Body {
+    /// Compiles `body`, reading the files it sends, and sets the matching `Content-Type` in
+    /// `headers` unless one is configured there.
+    pub(crate) fn compile(body: RequestBody, headers: &mut HeaderMap) -> Result<Body> {
+        let (body, content_type) = match body {
+            RequestBody::Text(text) => (Body::Text(Template::parse(text)?), None),
+            RequestBody::Json(json) => (
+                Body::Json(JsonTemplate::parse(json)?),
+                Some("application/json".to_string()),
+            ),
+            RequestBody::File(path) => (
+                Body::Static(read(path)?),
+                Some("application/octet-stream".to_string()),
+            ),
+            RequestBody::Form(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), Template::parse(value)?)))
+                    .collect::<Result<_>>()?;
+                (
+                    Body::Form(fields),
+                    Some("application/x-www-form-urlencoded".to_string()),
+                )
+            }
+            RequestBody::Multipart(fields) => {
+                let boundary = boundary();
+                let parts = fields
+                    .iter()
+                    .map(|field| Part::compile(field, &boundary))
+                    .collect::<Result<_>>()?;
+                let content_type = format!("multipart/form-data; boundary={}", boundary);
+                (Body::Multipart { boundary, parts }, Some(content_type))
+            }
+        };
+        if let Some(content_type) = content_type {
+            if !headers.contains_key(CONTENT_TYPE) {
+                headers.insert(CONTENT_TYPE, HeaderValue::from_str(&content_type)?);
+            }
+        }
+        Ok(body.fold())
+    }
+
+    /// The body of one request.
+    pub(crate) fn render(&self, ctx: &mut template::Context) -> Bytes {
+        self.build(|template| template.render(ctx))
+    }
+
+    /// Builds a body without templates once and for all.
+    fn fold(self) -> Body {
+        let is_static = match &self {
+            Body::Static(_) => false,
+            Body::Text(template) => template.as_static().is_some(),
+            Body::Json(json) => json.is_static(),
+            Body::Form(fields) => fields.iter().all(|(_, t)| t.as_static().is_some()),
+            Body::Multipart { parts, .. } => parts.iter().all(|part| match &part.content {
+                PartContent::Text(t) => t.as_static().is_some(),
+                PartContent::File(_) => true,
+            }),
+        };
+        if !is_static {
+            return self;
+        }
+        Body::Static(self.build(|template| template.as_static().unwrap_or_default().to_string()))
+    }
+
+    fn build(&self, mut text: impl FnMut(&Template) -> String) -> Bytes {
+        match self {
+            Body::Static(bytes) => bytes.clone(),
+            Body::Text(template) => Bytes::from(text(template)),
+            Body::Json(json) => {
+                let mut out = String::new();
+                json.write(&mut out, &mut text);
+                Bytes::from(out)
+            }
+            Body::Form(fields) => {
+                let mut form = form_urlencoded::Serializer::new(String::new());
+                for (key, template) in fields {
+                    form.append_pair(key, &text(template));
+                }
+                Bytes::from(form.finish())
+            }
+            Body::Multipart { boundary, parts } => {
+                let mut out = Vec::new();
+                for part in parts {
+                    out.extend_from_slice(part.head.as_bytes());
+                    match &part.content {
+                        PartContent::Text(template) => {
+                            out.extend_from_slice(text(template).as_bytes())
+                        }
+                        PartContent::File(bytes) => out.extend_from_slice(bytes),
+                    }
+                    out.extend_from_slice(b"\r\n");
+                }
+                out.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+                Bytes::from(out)
+            }
+        }
+    }
+}
+
+impl JsonTemplate {
+    pub(crate) fn parse(value: &serde_yaml::Value) -> Result<JsonTemplate> {
+        use serde_yaml::Value;
+        Ok(match value {
+            Value::String(s) => JsonTemplate::String(Template::parse(s)?),
+            Value::Sequence(items) => JsonTemplate::Array(
+                items
+                    .iter()
+                    .map(JsonTemplate::parse)
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Mapping(fields) => JsonTemplate::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = match key {
+                            Value::String(s) => s.clone(),
+                            Value::Number(n) => n.to_string(),
+                            Value::Bool(b) => b.to_string(),
+                            other => anyhow::bail!("Invalid JSON object key {:?}", other),
+                        };
+                        Ok((key, JsonTemplate::parse(value)?))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Tagged(tagged) => {
+                anyhow::bail!("YAML tag {} cannot be sent as JSON", tagged.tag)
+            }
+            scalar => JsonTemplate::Scalar(serde_json::to_string(scalar)?),
+        })
+    }
+
+    fn is_static(&self) -> bool {
+        match self {
+            JsonTemplate::Scalar(_) => true,
+            JsonTemplate::String(template) => template.as_static().is_some(),
+            JsonTemplate::Array(items) => items.iter().all(JsonTemplate::is_static),
+            JsonTemplate::Object(fields) => fields.iter().all(|(_, value)| value.is_static()),
+        }
+    }
+
+    fn write(&self, out: &mut String, text: &mut impl FnMut(&Template) -> String) {
+        match self {
+            JsonTemplate::Scalar(json) => out.push_str(json),
+            JsonTemplate::String(template) => {
+                out.push_str(&serde_json::Value::String(text(template)).to_string())
+            }
+            JsonTemplate::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    item.write(out, text);
+                }
+                out.push(']');
+            }
+            JsonTemplate::Object(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                    out.push(':');
+                    value.write(out, text);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+impl Part {
+    fn compile(field: &MultipartField, boundary: &str) -> Result<Part> {
+        let mut head = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            boundary,
+            quote(&field.name)
+        );
+        let content = match (&field.value, &field.file) {
+            (Some(value), _) => PartContent::Text(Template::parse(value).with_context(|| {
+                format!("Invalid template in multipart field '{}'", field.name)
+            })?),
+            (None, Some(path)) => {
+                let filename = field.filename.clone().unwrap_or_else(|| {
+                    std::path::Path::new(path)
+                        .file_name()
+                        .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned())
+                });
+                head.push_str(&format!("; filename=\"{}\"", quote(&filename)));
+                if field.content_type.is_none() {
+                    head.push_str("\r\nContent-Type: application/octet-stream");
+                }
+                PartContent::File(read(path)?)
+            }
+            (None, None) => {
+                anyhow::bail!("Multipart field '{}' has no `value` or `file`", field.name)
+            }
+        };
+        if let Some(content_type) = &field.content_type {
+            head.push_str(&format!("\r\nContent-Type: {}", content_type));
+        }
+        head.push_str("\r\n\r\n");
+        Ok(Part { head, content })
+    }
+}
+
+fn read(path: &str) -> Result<Bytes> {
+    let bytes = std::fs::read(path).with_context(|| format!("Cannot read '{}'", path))?;
+    Ok(Bytes::from(bytes))
+}
+
+/// A boundary that will not show up in the parts, in practice.
+fn boundary() -> String {
+    let suffix: String = rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect();
+    format!("goku-{}", suffix)
+}
+
+/// Escapes a name for a quoted `Content-Disposition` parameter, the way browsers do.
+fn quote(name: &str) -> String {
+    name.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+
+    fn render(body: &Body, seq: u32) -> Bytes {
+        let vars = HashMap::from([("user".to_string(), "ana & bo".to_string())]);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut ctx = template::Context {
+            seq,
+            client: 0,
+            vars: &vars,
+            rng: &mut rng,
+        };
+        body.render(&mut ctx)
+    }
+
+    #[test]
+    fn form_is_url_encoded_with_its_content_type() {
+        let form = [
+            ("user".to_string(), "{{user}}".to_string()),
+            ("n".to_string(), "{{seq}}".to_string()),
+            ("user".to_string(), "cy".to_string()),
+        ];
+        let mut headers = HeaderMap::new();
+        let body = Body::compile(RequestBody::Form(&form), &mut headers).unwrap();
+        assert_eq!(render(&body, 7).as_ref(), b"user=ana+%26+bo&n=7&user=cy");
+        assert_eq!(headers[CONTENT_TYPE], "application/x-www-form-urlencoded");
+
+        // A configured Content-Type is kept, and a constant form is built once.
+        let form = [("a".to_string(), "1".to_string())];
+        let mut headers =
+            HeaderMap::from_iter([(CONTENT_TYPE, HeaderValue::from_static("text/plain"))]);
+        let body = Body::compile(RequestBody::Form(&form), &mut headers).unwrap();
+        assert!(matches!(&body, Body::Static(bytes) if bytes.as_ref() == b"a=1"));
+        assert_eq!(headers[CONTENT_TYPE], "text/plain");
+    }
+
+    #[test]
+    fn json_body_renders_and_escapes_string_values() {
+        let json: serde_yaml::Value = serde_yaml::from_str(
+            "user: '{{user}} \"{{seq}}\"'\nids: [1, '{{seq}}']\nactive: true\n",
+        )
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        let body = Body::compile(RequestBody::Json(&json), &mut headers).unwrap();
+        assert_eq!(
+            render(&body, 7).as_ref(),
+            br#"{"user":"ana & bo \"7\"","ids":[1,"7"],"active":true}"#
+        );
+        assert_eq!(headers[CONTENT_TYPE], "application/json");
+
+        let json: serde_yaml::Value = serde_yaml::from_str("a: [~, 1.5]").unwrap();
+        let body = Body::compile(RequestBody::Json(&json), &mut headers).unwrap();
+        assert!(matches!(&body, Body::Static(bytes) if bytes.as_ref() == br#"{"a":[null,1.5]}"#));
+    }
+
+    #[test]
+    fn multipart_sends_text_and_file_parts() {
+        let path = std::env::temp_dir().join(format!("goku-body-{}.bin", std::process::id()));
+        std::fs::write(&path, b"\x89PNG").unwrap();
+        let fields = vec![
+            MultipartField {
+                name: "title".to_string(),
+                value: Some("photo {{seq}}".to_string()),
+                file: None,
+                filename: None,
+                content_type: None,
+            },
+            MultipartField {
+                name: "photo".to_string(),
+                value: None,
+                file: Some(path.to_string_lossy().into_owned()),
+                filename: None,
+                content_type: Some("image/png".to_string()),
+            },
+        ];
+        let mut headers = HeaderMap::new();
+        let body = Body::compile(RequestBody::Multipart(&fields), &mut headers).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let Body::Multipart { boundary, .. } = &body else {
+            panic!("expected a templated multipart body");
+        };
+        assert_eq!(
+            headers[CONTENT_TYPE].to_str().unwrap(),
+            format!("multipart/form-data; boundary={}", boundary)
+        );
+        let filename = path.file_name().unwrap().to_string_lossy();
+        let expected = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nphoto 3\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"{f}\"\r\n\
+             Content-Type: image/png\r\n\r\n\u{FFFD}PNG\r\n--{b}--\r\n",
+            b = boundary,
+            f = filename
+        );
+        assert_eq!(String::from_utf8_lossy(&render(&body, 3)), expected);
+    }
+
+    #[test]
+    fn file_body_is_sent_as_octet_stream() {
+        let mut headers = HeaderMap::new();
+        assert!(Body::compile(RequestBody::File("/nonexistent/goku.bin"), &mut headers).is_err());
+
+        let path = std::env::temp_dir().join(format!("goku-raw-{}.bin", std::process::id()));
+        std::fs::write(&path, [0u8, 159, 146, 150]).unwrap();
+        let body = Body::compile(RequestBody::File(&path.to_string_lossy()), &mut headers).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(render(&body, 0).as_ref(), &[0u8, 159, 146, 150]);
+        assert_eq!(headers[CONTENT_TYPE], "application/octet-stream");
+    }
+}
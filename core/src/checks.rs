@@ -0,0 +1,218 @@
+//! Response checks: compiled once per step, evaluated on every response.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use serde_json_path::JsonPath;
+
+use crate::benchmark::CheckOutcome;
+use crate::settings::Check;
+
+/// A [`Check`] with its regular expression or JSONPath parsed ahead of time.
+#[derive(Debug)]
+pub(crate) struct CompiledCheck {
+    /// Label used in reports.
+    name: Arc<str>,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Status(Vec<u16>),
+    BodyContains(String),
+    BodyMatches(Regex),
+    JsonPath {
+        path: JsonPath,
+        equals: serde_json::Value,
+    },
+    Header {
+        name: String,
+        matches: Option<Regex>,
+    },
+    MaxLatencyUs(u64),
+}
+
+/// The parts of a response checks are evaluated against.
+pub(crate) struct Response<'a> {
+    pub status: u16,
+    pub headers: &'a HeaderMap,
+    /// Empty unless the body was read.
+    pub body: &'a [u8],
+    pub duration_us: u64,
+}
+
+impl CompiledCheck {
+    /// Compiles `check`, reporting it under `name`.
+    pub(crate) fn new(check: &Check, name: String) -> Result<Self> {
+        let kind = match check {
+            Check::Status(codes) => Kind::Status(codes.clone()),
+            Check::BodyContains(text) => Kind::BodyContains(text.clone()),
+            Check::BodyMatches(re) => Kind::BodyMatches(
+                Regex::new(re).with_context(|| format!("Invalid regex '{}'", re))?,
+            ),
+            Check::JsonPath { path, equals } => Kind::JsonPath {
+                path: JsonPath::parse(path)
+                    .with_context(|| format!("Invalid JSONPath '{}'", path))?,
+                equals: equals.clone(),
+            },
+            Check::Header { name, matches } => Kind::Header {
+                name: name.clone(),
+                matches: matches
+                    .as_deref()
+                    .map(|re| Regex::new(re).with_context(|| format!("Invalid regex '{}'", re)))
+                    .transpose()?,
+            },
+            Check::MaxLatencyMs(ms) => Kind::MaxLatencyUs(ms.saturating_mul(1_000)),
+        };
+        Ok(CompiledCheck {
+            name: name.into(),
+            kind,
+        })
+    }
+
+    pub(crate) fn evaluate(&self, response: &Response) -> CheckOutcome {
+        let passed = match &self.kind {
+            Kind::Status(codes) => codes.contains(&response.status),
+            Kind::BodyContains(text) => {
+                String::from_utf8_lossy(response.body).contains(text.as_str())
+            }
+            Kind::BodyMatches(re) => re.is_match(&String::from_utf8_lossy(response.body)),
+            Kind::JsonPath { path, equals } => {
+                serde_json::from_slice::<serde_json::Value>(response.body)
+                    .ok()
+                    .and_then(|json| path.query(&json).first().map(|v| v == equals))
+                    .unwrap_or(false)
+            }
+            Kind::Header { name, matches } => {
+                let mut values = response.headers.get_all(name.as_str()).iter();
+                match matches {
+                    None => values.next().is_some(),
+                    Some(re) => values.any(|v| v.to_str().is_ok_and(|v| re.is_match(v))),
+                }
+            }
+            Kind::MaxLatencyUs(max) => response.duration_us <= *max,
+        };
+        CheckOutcome {
+            name: Arc::clone(&self.name),
+            passed,
+        }
+    }
+
+    pub(crate) fn needs_body(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::BodyContains(_) | Kind::BodyMatches(_) | Kind::JsonPath { .. }
+        )
+    }
+}
+
+/// Compiles the checks of one step. In multi-step scenarios, names are prefixed with the step
+/// label (see [`step_label`](crate::settings::step_label)) so identical checks on different
+/// steps are reported apart.
+pub(crate) fn compile(checks: &[Check], step: Option<&str>) -> Result<Vec<CompiledCheck>> {
+    checks
+        .iter()
+        .map(|check| {
+            let name = match step {
+                None => check.to_string(),
+                Some(label) => format!("{}: {}", label, check),
+            };
+            CompiledCheck::new(check, name)
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn eval(check: Check, status: u16, headers: &HeaderMap, body: &str) -> bool {
+        let compiled = CompiledCheck::new(&check, check.to_string()).unwrap();
+        compiled
+            .evaluate(&Response {
+                status,
+                headers,
+                body: body.as_bytes(),
+                duration_us: 2_000,
+            })
+            .passed
+    }
+
+    #[test]
+    fn status_check_matches_listed_codes() {
+        let h = HeaderMap::new();
+        assert!(eval(Check::Status(vec![200, 201]), 201, &h, ""));
+        assert!(!eval(Check::Status(vec![200]), 500, &h, ""));
+    }
+
+    #[test]
+    fn body_checks() {
+        let h = HeaderMap::new();
+        let body = r#"{"status":"error"}"#;
+        assert!(eval(Check::BodyContains("error".into()), 200, &h, body));
+        assert!(!eval(Check::BodyContains("ok".into()), 200, &h, body));
+        assert!(eval(
+            Check::BodyMatches(r#""status":"\w+""#.into()),
+            200,
+            &h,
+            body
+        ));
+    }
+
+    #[test]
+    fn json_path_check_compares_first_match() {
+        let h = HeaderMap::new();
+        let check = |equals: serde_json::Value| Check::JsonPath {
+            path: "$.data.id".into(),
+            equals,
+        };
+        let body = r#"{"data":{"id":42}}"#;
+        assert!(eval(check(serde_json::json!(42)), 200, &h, body));
+        assert!(!eval(check(serde_json::json!("42")), 200, &h, body));
+        assert!(!eval(check(serde_json::json!(42)), 200, &h, "not json"));
+    }
+
+    #[test]
+    fn header_check_presence_and_pattern() {
+        let mut h = HeaderMap::new();
+        h.insert("content-type", HeaderValue::from_static("application/json"));
+        let header = |name: &str, matches: Option<&str>| Check::Header {
+            name: name.into(),
+            matches: matches.map(String::from),
+        };
+        assert!(eval(header("Content-Type", None), 200, &h, ""));
+        assert!(eval(header("content-type", Some("json$")), 200, &h, ""));
+        assert!(!eval(header("content-type", Some("^text/")), 200, &h, ""));
+        assert!(!eval(header("x-missing", None), 200, &h, ""));
+    }
+
+    #[test]
+    fn max_latency_check() {
+        let h = HeaderMap::new();
+        assert!(eval(Check::MaxLatencyMs(2), 200, &h, ""));
+        assert!(!eval(Check::MaxLatencyMs(1), 200, &h, ""));
+    }
+
+    #[test]
+    fn compile_rejects_invalid_patterns() {
+        assert!(compile(&[Check::BodyMatches("(".into())], None).is_err());
+        let bad_path = Check::JsonPath {
+            path: "data.id".into(),
+            equals: serde_json::Value::Null,
+        };
+        assert!(compile(&[bad_path], None).is_err());
+    }
+
+    #[test]
+    fn compile_prefixes_step_number() {
+        let compiled = compile(&[Check::Status(vec![200])], Some("step 2")).unwrap();
+        assert_eq!(&*compiled[0].name, "step 2: status in [200]");
+    }
+}
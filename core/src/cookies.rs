@@ -0,0 +1,260 @@
+//! Minimal per-worker cookie jar (RFC 6265 storage and matching rules).
+
+use std::time::SystemTime;
+
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use reqwest::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cookie {
+    name: String,
+    value: String,
+    /// Lower-cased domain, without a leading dot.
+    domain: String,
+    /// Sent to subdomains too (a `Domain` attribute was given); otherwise only to `domain`.
+    include_subdomains: bool,
+    path: String,
+    secure: bool,
+    expires: Option<SystemTime>,
+}
+
+impl Cookie {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|e| e <= now)
+    }
+
+    fn matches(&self, url: &Url, now: SystemTime) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+        let domain_ok = host == self.domain
+            || (self.include_subdomains && host.ends_with(&format!(".{}", self.domain)));
+        domain_ok
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+            && !self.is_expired(now)
+    }
+}
+
+/// Cookies received by one worker, sent back on its later requests.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Stores the cookies of every `Set-Cookie` header of a response to `url`.
+    pub(crate) fn store(&mut self, url: &Url, headers: &HeaderMap) {
+        let now = SystemTime::now();
+        for header in headers.get_all(SET_COOKIE) {
+            let Ok(raw) = header.to_str() else {
+                continue;
+            };
+            let Some(cookie) = parse_set_cookie(raw, url, now) else {
+                continue;
+            };
+            self.cookies.retain(|c| {
+                !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+            });
+            if !cookie.is_expired(now) {
+                self.cookies.push(cookie);
+            }
+        }
+    }
+
+    /// Value for the `Cookie` request header to `url`, if any cookie applies. Longer paths
+    /// come first, as RFC 6265 recommends.
+    pub(crate) fn header_for(&mut self, url: &Url) -> Option<String> {
+        let now = SystemTime::now();
+        self.cookies.retain(|c| !c.is_expired(now));
+        let mut matching: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(url, now))
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+        let pairs: Vec<String> = matching
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.cookies.clear();
+    }
+}
+
+fn parse_set_cookie(raw: &str, url: &Url, now: SystemTime) -> Option<Cookie> {
+    let mut parts = raw.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let host = url.host_str()?.to_ascii_lowercase();
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().trim_matches('"').to_string(),
+        domain: host.clone(),
+        include_subdomains: false,
+        path: default_path(url),
+        secure: false,
+        expires: None,
+    };
+    let mut max_age = None;
+    for attr in parts {
+        let (key, val) = match attr.split_once('=') {
+            Some((k, v)) => (k.trim(), v.trim()),
+            None => (attr.trim(), ""),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "domain" if !val.is_empty() => {
+                let domain = val.trim_start_matches('.').to_ascii_lowercase();
+                // A server may only set cookies for its own domain or a parent of it.
+                if host != domain && !host.ends_with(&format!(".{}", domain)) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.include_subdomains = true;
+            }
+            "path" if val.starts_with('/') => cookie.path = val.to_string(),
+            "secure" => cookie.secure = true,
+            "max-age" => max_age = val.parse::<i64>().ok(),
+            "expires" => {
+                if let Ok(t) = httpdate::parse_http_date(val) {
+                    cookie.expires = Some(t);
+                }
+            }
+            _ => {}
+        }
+    }
+    // Max-Age takes precedence over Expires. One too large to represent never expires.
+    if let Some(secs) = max_age {
+        cookie.expires = if secs <= 0 {
+            Some(SystemTime::UNIX_EPOCH)
+        } else {
+            now.checked_add(std::time::Duration::from_secs(secs as u64))
+        };
+    }
+    Some(cookie)
+}
+
+/// Directory of the request path, used when `Set-Cookie` has no `Path`.
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => path[..i].to_string(),
+    }
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn set_cookies(values: &[&'static str]) -> HeaderMap {
+        let mut h = HeaderMap::new();
+        for v in values {
+            h.append(SET_COOKIE, HeaderValue::from_static(v));
+        }
+        h
+    }
+
+    fn url(s: &str) -> Url {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn stores_and_sends_cookies_back() {
+        let mut jar = CookieJar::default();
+        jar.store(
+            &url("http://shop.test/login"),
+            &set_cookies(&["sid=abc; Path=/; HttpOnly", "theme=dark"]),
+        );
+        assert_eq!(
+            jar.header_for(&url("http://shop.test/cart")).as_deref(),
+            Some("sid=abc; theme=dark")
+        );
+        assert_eq!(jar.header_for(&url("http://other.test/")), None);
+    }
+
+    #[test]
+    fn newer_cookie_replaces_older_and_max_age_zero_deletes() {
+        let mut jar = CookieJar::default();
+        let u = url("http://shop.test/");
+        jar.store(&u, &set_cookies(&["sid=1"]));
+        jar.store(&u, &set_cookies(&["sid=2"]));
+        assert_eq!(jar.header_for(&u).as_deref(), Some("sid=2"));
+        jar.store(&u, &set_cookies(&["sid=; Max-Age=0"]));
+        assert_eq!(jar.header_for(&u), None);
+    }
+
+    #[test]
+    fn domain_path_and_secure_rules() {
+        let mut jar = CookieJar::default();
+        jar.store(
+            &url("https://www.shop.test/account/login"),
+            &set_cookies(&[
+                "wide=1; Domain=shop.test; Path=/",
+                "scoped=2",
+                "safe=3; Secure; Path=/",
+                "foreign=4; Domain=evil.test",
+            ]),
+        );
+        // `scoped` defaults to the /account path and to www.shop.test only.
+        assert_eq!(
+            jar.header_for(&url("https://www.shop.test/account/orders"))
+                .as_deref(),
+            Some("scoped=2; wide=1; safe=3")
+        );
+        assert_eq!(
+            jar.header_for(&url("http://api.shop.test/")).as_deref(),
+            Some("wide=1")
+        );
+        assert_eq!(jar.header_for(&url("https://evil.test/")), None);
+    }
+
+    #[test]
+    fn expired_cookies_are_dropped() {
+        let mut jar = CookieJar::default();
+        let u = url("http://shop.test/");
+        jar.store(
+            &u,
+            &set_cookies(&["old=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT"]),
+        );
+        assert_eq!(jar.header_for(&u), None);
+    }
+
+    #[test]
+    fn huge_max_age_never_expires() {
+        let mut jar = CookieJar::default();
+        let u = url("http://shop.test/");
+        jar.store(&u, &set_cookies(&["sid=1; Max-Age=9223372036854775807"]));
+        assert_eq!(jar.header_for(&u).as_deref(), Some("sid=1"));
+    }
+
+    #[test]
+    fn path_matching() {
+        assert!(path_matches("/a/b", "/a"));
+        assert!(path_matches("/a/", "/a/"));
+        assert!(!path_matches("/ab", "/a"));
+        assert!(path_matches("/anything", "/"));
+    }
+}
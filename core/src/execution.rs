@@ -1,139 +1,531 @@
+use std::collections::HashMap;
+use std::error::Error as _;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
-use reqwest::{Client, Url};
-use tokio::sync::mpsc::Sender;
-use tokio::sync::watch::Receiver;
-use tokio::time::{self, Instant};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE};
+use reqwest::{Client, Method, Url};
+use tokio::sync::mpsc::{self, Sender, UnboundedReceiver, UnboundedSender};
+use tokio::sync::watch::{self, Receiver};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant, MissedTickBehavior};
 
-use crate::benchmark::BenchmarkResult;
-use crate::settings::{Operation, Settings, Step};
+use crate::benchmark::{BenchmarkResult, ErrorKind, Outcome, Phases, TransactionOutcome};
+use crate::body::Body;
+use crate::checks::{self, CompiledCheck};
+use crate::cookies::CookieJar;
+use crate::extract::{self, CompiledExtractor};
+use crate::feeders::Feeds;
+use crate::settings::{step_label, Executor, Settings, Step, ThinkTime};
+use crate::stages::Schedule;
+use crate::template::{self, Template};
+use crate::timing::{self, ConnectTimingLayer, TimedResolver};
+
+/// How often the number of active workers is adjusted to a `clients` stage profile.
+const STAGE_TICK: std::time::Duration = std::time::Duration::from_millis(100);
+/// How often the arrival generator of an `rps` stage profile starts the iterations due.
+const ARRIVAL_TICK: std::time::Duration = std::time::Duration::from_millis(1);
 
 // ---------------------------------------------------------------------------
 // Public entry point
 // ---------------------------------------------------------------------------
 
+/// Starts the load and returns once every worker has been spawned; results are sent on `tx`,
+/// which closes when the run (teardown included) is over.
 pub async fn run(
     settings: Settings,
     tx: Sender<BenchmarkResult>,
     rx_sigint: Option<Receiver<Option<()>>>,
-) -> Result<()> {
-    let mut builder = Client::builder()
-        .timeout(settings.timeout)
-        .danger_accept_invalid_certs(settings.insecure)
-        .pool_max_idle_per_host(settings.clients as usize);
-
-    // Pool idle timeout (default: 90 s, configurable)
-    let pool_idle = settings
-        .pool_idle_timeout
-        .map(std::time::Duration::from_secs)
-        .unwrap_or(std::time::Duration::from_secs(90));
-
-    if settings.disable_keepalive {
-        builder = builder
-            .tcp_keepalive(None)
-            .pool_idle_timeout(std::time::Duration::ZERO)
-            .connection_verbose(false);
-    } else {
-        builder = builder
-            .tcp_keepalive(settings.keep_alive)
-            .pool_idle_timeout(pool_idle);
-    }
-
-    if settings.http2 {
-        builder = builder.http2_prior_knowledge();
-    }
-
-    let client = Arc::new(
-        builder
-            .build()
-            .with_context(|| "Cannot create HTTP client".to_string())?,
-    );
-
+) -> Result<Teardown> {
+    let mut clients = Clients::default();
     let settings = Arc::new(settings);
 
     // Build the shared header map (includes auth header if configured)
-    let headers_map: Arc<HeaderMap> =
-        Arc::new(build_headers(&settings).with_context(|| "Failed to build request headers")?);
+    let headers_map =
+        build_headers(&settings).with_context(|| "Failed to build request headers")?;
+
+    let feeds = Feeds::load(&settings)?;
 
     // Decide execution mode: multi-step scenario vs single target
-    let steps: Arc<Vec<StepResolved>> = if settings.steps.is_empty() {
+    let mut plan = if !settings.is_scenario() {
         // Single-target mode: treat as a one-step scenario
-        let url = settings
-            .target_url()
-            .parse::<Url>()
-            .with_context(|| format!("Invalid URL: {}", settings.target_url()))?;
-        Arc::new(vec![StepResolved {
-            operation: settings.operation(),
-            url,
-            body: settings.body.clone(),
-            extra_headers: HeaderMap::new(),
-        }])
+        let url = settings.target_url();
+        url.parse::<Url>()
+            .with_context(|| format!("Invalid URL: {}", url))?;
+        let mut headers = headers_map.clone();
+        let body = settings
+            .request_body()?
+            .map(|body| Body::compile(body, &mut headers))
+            .transpose()
+            .context("Invalid body")?;
+        let (headers, header_templates) = compile_headers(headers)?;
+        Plan::new(
+            feeds,
+            vec![FlowResolved {
+                weight: 1,
+                steps: vec![StepResolved {
+                    method: settings.operation()?.method(),
+                    client: clients.get(&settings, settings.http2, settings.insecure)?,
+                    timeout: None,
+                    url: Template::parse(&url)?,
+                    body,
+                    headers,
+                    header_templates,
+                    checks: checks::compile(&settings.checks, None)?,
+                    extractors: vec![],
+                    think_time: settings.think_time.clone(),
+                    flow: None,
+                    index: None,
+                    last: true,
+                    transaction: None,
+                    transaction_end: false,
+                }],
+            }],
+        )
     } else {
-        // Multi-step mode
-        let mut resolved = Vec::with_capacity(settings.steps.len());
-        for step in &settings.steps {
-            let url = step
-                .url()
-                .parse::<Url>()
-                .with_context(|| format!("Invalid URL in step: {}", step.url()))?;
-            let extra_headers = build_step_headers(step)
-                .with_context(|| format!("Invalid headers in step '{}'", step.target))?;
-            resolved.push(StepResolved {
-                operation: step.operation(),
-                url,
-                body: step.body.clone(),
-                extra_headers,
+        // Multi-step mode: `steps` is a single unnamed flow
+        let named = !settings.flows.is_empty();
+        let transactions = settings.transactions();
+        let metrics = settings.step_metrics();
+        let mut flows = vec![];
+        for (f, (name, steps)) in settings.step_groups().into_iter().enumerate() {
+            let mut resolved = Vec::with_capacity(steps.len());
+            for (i, step) in steps.iter().enumerate() {
+                let label = step_label(name, i);
+                let transaction = step.transaction.as_deref();
+                let metrics_name = step.metrics_name();
+                resolved.push(StepResolved {
+                    flow: named.then_some(f),
+                    index: metrics.iter().position(|(name, _)| *name == metrics_name),
+                    last: i == steps.len() - 1,
+                    transaction: transaction
+                        .and_then(|name| transactions.iter().position(|t| *t == name)),
+                    transaction_end: transaction.is_some()
+                        && steps.get(i + 1).and_then(|s| s.transaction.as_deref()) != transaction,
+                    ..resolve_step(&settings, &mut clients, &headers_map, step, &label)?
+                });
+            }
+            flows.push(FlowResolved {
+                weight: settings.flows.get(f).map_or(1, |flow| flow.weight),
+                steps: resolved,
             });
         }
-        Arc::new(resolved)
+        Plan::new(feeds, flows)
     };
 
-    // Rate limiter: tokens per ms per worker (None = unlimited)
-    let ramp_up_delay = settings.ramp_up.map(|secs| {
-        let total_ms = secs * 1000;
-        let clients = settings.clients.max(1) as u64;
-        std::time::Duration::from_millis(total_ms / clients)
-    });
+    let mut resolve_phase = |phase: &str, steps: &[Step]| {
+        steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let label = step_label(Some(phase), i);
+                resolve_step(&settings, &mut clients, &headers_map, step, &label)
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+    let setup = resolve_phase("setup", &settings.setup)?;
+    let teardown = resolve_phase("teardown", &settings.teardown)?;
+    plan.globals = run_setup(&settings, &plan, &setup).await?;
+    let plan = Arc::new(plan);
 
-    for id in 0..settings.clients {
-        if id > 0 {
-            if let Some(delay) = ramp_up_delay {
-                tokio::time::sleep(delay).await;
-            }
+    let mut workers = vec![];
+    if !settings.stages.is_empty() {
+        let schedule = Schedule::new(&settings.stages);
+        if settings.has_rps_stages() {
+            workers.push(tokio::spawn(ramping_arrival_rate(
+                Arc::clone(&settings),
+                Arc::clone(&plan),
+                tx.clone(),
+                rx_sigint,
+                schedule,
+            )));
+        } else {
+            workers.push(tokio::spawn(ramping_workers(
+                Arc::clone(&settings),
+                Arc::clone(&plan),
+                tx.clone(),
+                rx_sigint,
+                schedule,
+            )));
         }
-        tokio::spawn(exec_iterator(
-            id as usize,
+    } else if settings.executor == Executor::ConstantArrivalRate {
+        workers.push(tokio::spawn(constant_arrival_rate(
             Arc::clone(&settings),
-            Arc::clone(&client),
-            Arc::clone(&steps),
-            Arc::clone(&headers_map),
+            Arc::clone(&plan),
             tx.clone(),
-            rx_sigint.clone(),
-        ));
+            rx_sigint,
+        )));
+    } else {
+        // Rate limiter: tokens per ms per worker (None = unlimited)
+        let ramp_up_delay = settings.ramp_up.map(|secs| {
+            let total_ms = secs * 1000;
+            let clients = settings.clients.max(1) as u64;
+            std::time::Duration::from_millis(total_ms / clients)
+        });
+
+        for id in 0..settings.clients {
+            if id > 0 {
+                if let Some(delay) = ramp_up_delay {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+            workers.push(tokio::spawn(exec_iterator(
+                id as usize,
+                Arc::clone(&settings),
+                Arc::clone(&plan),
+                tx.clone(),
+                rx_sigint.clone(),
+            )));
+        }
+    }
+
+    if teardown.is_empty() {
+        return Ok(Teardown(None));
     }
-    Ok(())
+    let handle = tokio::spawn(run_teardown(settings, plan, teardown, workers, tx));
+    Ok(Teardown(Some(handle)))
+}
+
+/// Compiles a scenario step, on top of the global headers. The step is not part of a flow;
+/// callers set `flow`, `index`, `last` and the transaction fields when it is.
+fn resolve_step(
+    settings: &Settings,
+    clients: &mut Clients,
+    headers_map: &HeaderMap,
+    step: &Step,
+    label: &str,
+) -> Result<StepResolved> {
+    let url = step.url();
+    url.parse::<Url>()
+        .with_context(|| format!("Invalid URL in {}: {}", label, url))?;
+    let mut headers = headers_map.clone();
+    headers.extend(
+        build_step_headers(step)
+            .with_context(|| format!("Invalid headers in step '{}'", step.target))?,
+    );
+    let body = step
+        .request_body()?
+        .map(|body| Body::compile(body, &mut headers))
+        .transpose()
+        .with_context(|| format!("Invalid body in {}", label))?;
+    let (headers, header_templates) =
+        compile_headers(headers).with_context(|| format!("Invalid header in {}", label))?;
+    Ok(StepResolved {
+        method: step
+            .operation()
+            .with_context(|| format!("Invalid method in {}", label))?
+            .method(),
+        client: clients.get(
+            settings,
+            step.http2.unwrap_or(settings.http2),
+            step.insecure.unwrap_or(settings.insecure),
+        )?,
+        timeout: step.timeout_ms.map(std::time::Duration::from_millis),
+        url: Template::parse(&url).with_context(|| format!("Invalid URL in {}", label))?,
+        body,
+        headers,
+        header_templates,
+        checks: checks::compile(&step.checks, Some(label))?,
+        extractors: extract::compile(&step.extract, label)?,
+        think_time: step
+            .think_time
+            .clone()
+            .or_else(|| settings.think_time.clone()),
+        flow: None,
+        index: None,
+        last: false,
+        transaction: None,
+        transaction_end: false,
+    })
 }
 
 // ---------------------------------------------------------------------------
 // Internal types
 // ---------------------------------------------------------------------------
 
-/// A pre-resolved step with parsed URL and operation.
+/// A pre-resolved step with its operation, checks and extractors. The URL is kept as a
+/// template and parsed per request, after variable substitution.
 struct StepResolved {
-    operation: Operation,
-    url: Url,
-    body: Option<String>,
-    extra_headers: HeaderMap,
+    method: Method,
+    /// Client with the step's protocol options.
+    client: Client,
+    /// Request timeout overriding the client's.
+    timeout: Option<std::time::Duration>,
+    url: Template,
+    body: Option<Body>,
+    /// Global and step headers, plus the Content-Type of `body` unless one is configured; the
+    /// values of `header_templates` are rendered over them.
+    headers: HeaderMap,
+    header_templates: Vec<(HeaderName, Template)>,
+    checks: Vec<CompiledCheck>,
+    extractors: Vec<CompiledExtractor>,
+    /// Pause after the step: the step's own or else the scenario's.
+    think_time: Option<ThinkTime>,
+    /// Index of the named flow the step belongs to (`None` without `flows`).
+    flow: Option<usize>,
+    /// Index of the step's metrics, in `Settings::step_metrics` order (`None` outside the
+    /// scenario steps).
+    index: Option<usize>,
+    /// Whether the step ends its flow, and so the iteration.
+    last: bool,
+    /// Index of the transaction the step belongs to, in `Settings::transactions` order.
+    transaction: Option<usize>,
+    /// Whether the step is the last one of its transaction.
+    transaction_end: bool,
+}
+
+/// A sequence of steps run as one iteration, drawn with a probability proportional to `weight`.
+struct FlowResolved {
+    weight: u32,
+    steps: Vec<StepResolved>,
+}
+
+/// The flows workers draw their iterations from, and the test data fed to them. A single
+/// target or a plain `steps` list is one flow.
+struct Plan {
+    flows: Vec<FlowResolved>,
+    total_weight: u32,
+    feeds: Feeds,
+    /// Values extracted by the `setup` steps, which every worker starts from.
+    globals: HashMap<String, String>,
+}
+
+impl Plan {
+    fn new(feeds: Feeds, flows: Vec<FlowResolved>) -> Self {
+        let total_weight = flows.iter().map(|f| f.weight).sum();
+        Plan {
+            flows,
+            total_weight,
+            feeds,
+            globals: HashMap::new(),
+        }
+    }
+
+    /// Draws the flow of a new iteration, in proportion to the weights.
+    fn pick_flow(&self, rng: &mut SmallRng) -> usize {
+        if self.flows.len() == 1 {
+            return 0;
+        }
+        let mut ticket = rng.random_range(0..self.total_weight);
+        for (i, flow) in self.flows.iter().enumerate() {
+            if ticket < flow.weight {
+                return i;
+            }
+            ticket -= flow.weight;
+        }
+        self.flows.len() - 1
+    }
+}
+
+/// State one worker (virtual user) keeps across its requests.
+#[derive(Debug)]
+struct VirtualUser {
+    id: usize,
+    /// Values captured by extractors, available to later requests as `{{name}}`, plus the
+    /// current feeder rows as `{{feeder.column}}`.
+    vars: HashMap<String, String>,
+    /// Rows taken from each feeder so far.
+    feed_draws: Vec<usize>,
+    /// Present when `cookies` is enabled.
+    cookies: Option<CookieJar>,
+    /// Draws flows and random think times.
+    rng: SmallRng,
+    /// Flow of the current iteration and index of its next step.
+    flow: usize,
+    pos: usize,
+    /// Start of the current scenario iteration, when iterations are timed (several steps or
+    /// think time configured).
+    iteration_start: Option<Instant>,
+    track_iterations: bool,
+    /// Start of the current transaction, and whether one of its requests failed so far.
+    transaction_start: Option<Instant>,
+    transaction_failed: bool,
+}
+
+impl VirtualUser {
+    fn new(id: usize, settings: &Settings, plan: &Plan) -> Self {
+        VirtualUser {
+            id,
+            vars: plan.globals.clone(),
+            feed_draws: vec![0; plan.feeds.len()],
+            cookies: settings.cookies.then(CookieJar::default),
+            rng: worker_rng(settings.seed, id),
+            flow: 0,
+            pos: 0,
+            iteration_start: None,
+            track_iterations: settings.steps.len() > 1
+                || !settings.flows.is_empty()
+                || settings.think_time.is_some()
+                || settings.all_steps().any(|s| s.think_time.is_some()),
+            transaction_start: None,
+            transaction_failed: false,
+        }
+    }
+
+    /// Returns the step to run next: the following step of the current flow, or the first step
+    /// of a flow drawn by weight when a new iteration starts. `None` once a feeder set to
+    /// `stop` has run out of rows, which ends the worker.
+    fn next_step<'p>(&mut self, settings: &Settings, plan: &'p Plan) -> Option<&'p StepResolved> {
+        if self.pos == 0 {
+            self.flow = plan.pick_flow(&mut self.rng);
+            self.start_iteration(settings, plan);
+            if !plan
+                .feeds
+                .draw(self.id, &mut self.feed_draws, &mut self.rng, &mut self.vars)
+            {
+                return None;
+            }
+        }
+        let steps = &plan.flows[self.flow].steps;
+        let step = &steps[self.pos];
+        self.pos = (self.pos + 1) % steps.len();
+        if step.transaction.is_some() && self.transaction_start.is_none() {
+            self.transaction_start = Some(Instant::now());
+            self.transaction_failed = false;
+        }
+        Some(step)
+    }
+
+    /// Whether the next step starts a new iteration.
+    fn at_iteration_start(&self) -> bool {
+        self.pos == 0
+    }
+
+    /// With `reset_session`, every iteration starts from a clean session (keeping the values
+    /// extracted by `setup`).
+    fn start_iteration(&mut self, settings: &Settings, plan: &Plan) {
+        if self.track_iterations {
+            self.iteration_start = Some(Instant::now());
+        }
+        if settings.reset_session {
+            self.vars.clone_from(&plan.globals);
+            if let Some(jar) = &mut self.cookies {
+                jar.clear();
+            }
+        }
+    }
+
+    /// Called once a step's response is in: pauses for the step's think time and records it,
+    /// plus the durations of the iteration and transaction the step ends, on `result`.
+    async fn after_step(&mut self, step: &StepResolved, result: &mut BenchmarkResult) {
+        if let Some(think_time) = &step.think_time {
+            let pause = sample_think_time(think_time, &mut self.rng);
+            time::sleep(pause).await;
+            result.think_time_us = pause.as_micros() as u64;
+        }
+        if step.last {
+            result.iteration_us = self
+                .iteration_start
+                .take()
+                .map(|start| start.elapsed().as_micros() as u64);
+        }
+        if let Some(index) = step.transaction {
+            self.transaction_failed |= result.is_error() || result.failed_checks();
+            if step.transaction_end {
+                result.transaction =
+                    self.transaction_start
+                        .take()
+                        .map(|start| TransactionOutcome {
+                            index,
+                            duration_us: start.elapsed().as_micros() as u64,
+                            failed: self.transaction_failed,
+                        });
+            }
+        }
+    }
+}
+
+/// Random number generator of worker `id`. With a seed, each worker gets its own stream,
+/// derived from the seed and the worker id, so runs are reproducible and workers do not
+/// share values.
+fn worker_rng(seed: Option<u64>, id: usize) -> SmallRng {
+    match seed {
+        None => SmallRng::from_os_rng(),
+        Some(seed) => SmallRng::seed_from_u64(splitmix64(seed ^ splitmix64(id as u64))),
+    }
+}
+
+/// SplitMix64 finalizer: spreads nearby inputs (consecutive worker ids) over the whole range.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Draws one pause from a think-time distribution.
+fn sample_think_time(think_time: &ThinkTime, rng: &mut SmallRng) -> std::time::Duration {
+    let ms = match think_time {
+        ThinkTime::FixedMs(ms) => *ms as f64,
+        ThinkTime::UniformMs { min, max } => rng.random_range(*min..=*max) as f64,
+        // Inverse transform sampling; `1 - u` keeps the logarithm finite.
+        ThinkTime::ExponentialMs(mean) => -(*mean as f64) * (1.0 - rng.random::<f64>()).ln(),
+    };
+    std::time::Duration::from_secs_f64(ms / 1_000.0)
 }
 
 // ---------------------------------------------------------------------------
 // Header builders
 // ---------------------------------------------------------------------------
 
+/// The HTTP clients of a run, one per combination of protocol options used by its steps.
+#[derive(Default)]
+struct Clients {
+    built: HashMap<(bool, bool), Client>,
+}
+
+impl Clients {
+    fn get(&mut self, settings: &Settings, http2: bool, insecure: bool) -> Result<Client> {
+        if let Some(client) = self.built.get(&(http2, insecure)) {
+            return Ok(client.clone());
+        }
+        let client = build_client(settings, http2, insecure)?;
+        self.built.insert((http2, insecure), client.clone());
+        Ok(client)
+    }
+}
+
+fn build_client(settings: &Settings, http2: bool, insecure: bool) -> Result<Client> {
+    // DNS, connect and TLS hooks feed the per-request phase breakdown (see `timing`).
+    // `--insecure` is applied by the TLS configuration itself.
+    let mut builder = Client::builder()
+        .timeout(settings.timeout)
+        .pool_max_idle_per_host(settings.peak_clients() as usize)
+        .dns_resolver(Arc::new(TimedResolver))
+        .connector_layer(ConnectTimingLayer)
+        .tls_backend_preconfigured(timing::tls_config(http2, insecure)?);
+
+    // Pool idle timeout (default: 90 s, configurable)
+    let pool_idle = settings
+        .pool_idle_timeout
+        .map(std::time::Duration::from_secs)
+        .unwrap_or(std::time::Duration::from_secs(90));
+
+    if settings.disable_keepalive {
+        builder = builder
+            .tcp_keepalive(None)
+            .pool_idle_timeout(std::time::Duration::ZERO)
+            .connection_verbose(false);
+    } else {
+        builder = builder
+            .tcp_keepalive(settings.keep_alive)
+            .pool_idle_timeout(pool_idle);
+    }
+
+    if http2 {
+        builder = builder.http2_prior_knowledge();
+    }
+
+    builder
+        .build()
+        .with_context(|| "Cannot create HTTP client".to_string())
+}
+
 fn build_headers(settings: &Settings) -> Result<HeaderMap> {
     let mut map = HeaderMap::new();
 
@@ -158,6 +550,27 @@ fn build_headers(settings: &Settings) -> Result<HeaderMap> {
     Ok(map)
 }
 
+/// Splits the templated values out of `headers`, to be rendered for every request. Values
+/// whose template is constant are replaced by its text right away.
+fn compile_headers(mut headers: HeaderMap) -> Result<(HeaderMap, Vec<(HeaderName, Template)>)> {
+    let mut templates = vec![];
+    for (name, value) in headers.iter_mut() {
+        let Ok(text) = value.to_str() else {
+            continue;
+        };
+        let template = Template::parse(text)?;
+        match template.as_static() {
+            Some(constant) if constant == text => {}
+            Some(constant) => {
+                *value = HeaderValue::from_str(constant)
+                    .with_context(|| format!("Invalid value for header '{}'", name))?;
+            }
+            None => templates.push((name.clone(), template)),
+        }
+    }
+    Ok((headers, templates))
+}
+
 fn build_step_headers(step: &Step) -> Result<HeaderMap> {
     let mut map = HeaderMap::new();
     if let Some(headers) = &step.headers {
@@ -169,6 +582,11 @@ fn build_step_headers(step: &Step) -> Result<HeaderMap> {
             map.insert(name, value);
         }
     }
+    if let Some(auth) = &step.auth {
+        let value = HeaderValue::from_str(&auth.header_value())
+            .with_context(|| "Invalid Authorization header value")?;
+        map.insert(AUTHORIZATION, value);
+    }
     Ok(map)
 }
 
@@ -179,49 +597,36 @@ fn build_step_headers(step: &Step) -> Result<HeaderMap> {
 async fn exec_iterator(
     num_client: usize,
     settings: Arc<Settings>,
-    client: Arc<Client>,
-    steps: Arc<Vec<StepResolved>>,
-    headers_map: Arc<HeaderMap>,
+    plan: Arc<Plan>,
     tx: Sender<BenchmarkResult>,
     mut rx_sigint: Option<Receiver<Option<()>>>,
 ) {
-    // Rate limiting interval per worker (if rps is configured)
-    let rate_interval: Option<std::time::Duration> = settings.rps.and_then(|rps| {
+    // Rate limiting schedule per worker (if rps is configured)
+    let pacer: Option<Pacer> = settings.rps.and_then(|rps| {
         if rps == 0 {
             None
         } else {
             // Spread RPS evenly across clients
             let rps_per_client = (rps as f64 / settings.clients as f64).max(0.001);
-            let interval_ms = (1000.0 / rps_per_client) as u64;
-            Some(std::time::Duration::from_millis(interval_ms))
+            Some(Pacer::new(std::time::Duration::from_secs_f64(
+                1.0 / rps_per_client,
+            )))
         }
     });
 
     match settings.duration {
         None => {
-            by_iterations(
-                num_client,
-                &settings,
-                &client,
-                &steps,
-                &headers_map,
-                &tx,
-                &mut rx_sigint,
-                rate_interval,
-            )
-            .await;
+            by_iterations(num_client, &settings, &plan, &tx, &mut rx_sigint, pacer).await;
         }
         Some(duration) => {
             by_time(
                 num_client,
                 &settings,
-                &client,
-                &steps,
-                &headers_map,
+                &plan,
                 tx,
                 &mut rx_sigint,
                 duration,
-                rate_interval,
+                pacer,
             )
             .await;
         }
@@ -236,40 +641,64 @@ async fn exec_iterator(
 async fn by_time(
     num_client: usize,
     settings: &Settings,
-    client: &Client,
-    steps: &[StepResolved],
-    headers_map: &HeaderMap,
+    plan: &Plan,
     tx: Sender<BenchmarkResult>,
     rx_sigint: &mut Option<Receiver<Option<()>>>,
     duration_secs: u64,
-    rate_interval: Option<std::time::Duration>,
+    mut pacer: Option<Pacer>,
 ) {
     let begin = Instant::now();
     let mut execution_number: u32 = 0;
-    let mut step_idx = 0usize;
+    let mut user = VirtualUser::new(num_client, settings, plan);
 
     while begin.elapsed().as_secs() < duration_secs {
         // Rate limiting
-        if let Some(interval) = rate_interval {
-            time::sleep(interval).await;
-        }
+        let intended = match &mut pacer {
+            Some(p) => Some(p.wait().await),
+            None => None,
+        };
 
-        let step = &steps[step_idx % steps.len()];
-        step_idx += 1;
+        let Some(step) = user.next_step(settings, plan) else {
+            break;
+        };
 
         match rx_sigint {
             None => {
-                let result =
-                    exec(num_client, execution_number, client, step, headers_map, settings).await;
+                let mut result = exec(
+                    num_client,
+                    execution_number,
+                    step,
+                    settings,
+                    intended,
+                    &mut user,
+                )
+                .await;
+                user.after_step(step, &mut result).await;
+                if let Some(p) = &mut pacer {
+                    p.pause(result.think_time_us);
+                }
                 let _ = tx.send(result).await;
                 execution_number += 1;
             }
             Some(rx) => {
                 let stop_signal = rx.changed();
-                let result =
-                    exec(num_client, execution_number, client, step, headers_map, settings).await;
+                let mut result = exec(
+                    num_client,
+                    execution_number,
+                    step,
+                    settings,
+                    intended,
+                    &mut user,
+                )
+                .await;
                 execution_number += 1;
-                let ack = tx.send(result);
+                let ack = async {
+                    user.after_step(step, &mut result).await;
+                    if let Some(p) = &mut pacer {
+                        p.pause(result.think_time_us);
+                    }
+                    tx.send(result).await
+                };
                 match tokio::select! {
                     _ = ack => None,
                     _ = stop_signal => Some(()),
@@ -290,34 +719,60 @@ async fn by_time(
 async fn by_iterations(
     num_client: usize,
     settings: &Settings,
-    client: &Client,
-    steps: &[StepResolved],
-    headers_map: &HeaderMap,
+    plan: &Plan,
     tx: &Sender<BenchmarkResult>,
     rx_sigint: &mut Option<Receiver<Option<()>>>,
-    rate_interval: Option<std::time::Duration>,
+    mut pacer: Option<Pacer>,
 ) {
     let total = settings.requests_by_client();
+    let mut user = VirtualUser::new(num_client, settings, plan);
 
     for execution_number in 0..total {
         // Rate limiting
-        if let Some(interval) = rate_interval {
-            time::sleep(interval).await;
-        }
+        let intended = match &mut pacer {
+            Some(p) => Some(p.wait().await),
+            None => None,
+        };
 
-        let step = &steps[(execution_number as usize) % steps.len()];
+        let Some(step) = user.next_step(settings, plan) else {
+            break;
+        };
 
         match rx_sigint {
             None => {
-                let result =
-                    exec(num_client, execution_number, client, step, headers_map, settings).await;
+                let mut result = exec(
+                    num_client,
+                    execution_number,
+                    step,
+                    settings,
+                    intended,
+                    &mut user,
+                )
+                .await;
+                user.after_step(step, &mut result).await;
+                if let Some(p) = &mut pacer {
+                    p.pause(result.think_time_us);
+                }
                 let _ = tx.send(result).await;
             }
             Some(rx) => {
-                let result =
-                    exec(num_client, execution_number, client, step, headers_map, settings).await;
+                let mut result = exec(
+                    num_client,
+                    execution_number,
+                    step,
+                    settings,
+                    intended,
+                    &mut user,
+                )
+                .await;
                 let stop_signal = rx.changed();
-                let ack = tx.send(result);
+                let ack = async {
+                    user.after_step(step, &mut result).await;
+                    if let Some(p) = &mut pacer {
+                        p.pause(result.think_time_us);
+                    }
+                    tx.send(result).await
+                };
                 match tokio::select! {
                     _ = ack => None,
                     _ = stop_signal => Some(()),
@@ -330,165 +785,753 @@ async fn by_iterations(
     }
 }
 
+// ---------------------------------------------------------------------------
+// ramping_workers (`clients` stages)
+// ---------------------------------------------------------------------------
+
+/// Runs a `clients` stage profile: one worker per peak client is spawned up front, and the
+/// number of active ones follows the schedule. The workers above the current level park at
+/// the end of their iteration and resume when the level rises again.
+async fn ramping_workers(
+    settings: Arc<Settings>,
+    plan: Arc<Plan>,
+    tx: Sender<BenchmarkResult>,
+    mut rx_sigint: Option<Receiver<Option<()>>>,
+    schedule: Schedule,
+) {
+    // (current stage, number of active workers)
+    let (level_tx, level_rx) = watch::channel((0usize, 0usize));
+    let workers: Vec<JoinHandle<()>> = (0..settings.peak_clients())
+        .map(|id| {
+            tokio::spawn(staged_worker(
+                id as usize,
+                Arc::clone(&settings),
+                Arc::clone(&plan),
+                level_rx.clone(),
+                tx.clone(),
+            ))
+        })
+        .collect();
+
+    let begin = Instant::now();
+    let mut ticker = time::interval(STAGE_TICK);
+    while let Some((stage, level)) = schedule.at(begin.elapsed()) {
+        if plan.feeds.exhausted() {
+            break;
+        }
+        let level = (stage, level.round() as usize);
+        level_tx.send_if_modified(|current| std::mem::replace(current, level) != level);
+        match &mut rx_sigint {
+            None => {
+                ticker.tick().await;
+            }
+            Some(rx) => {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = rx.changed() => break,
+                }
+            }
+        }
+    }
+    // Dropping `level_tx` stops the workers once their current request is done.
+    drop(level_tx);
+    join_all(workers).await;
+}
+
+async fn staged_worker(
+    num_client: usize,
+    settings: Arc<Settings>,
+    plan: Arc<Plan>,
+    mut level: watch::Receiver<(usize, usize)>,
+    tx: Sender<BenchmarkResult>,
+) {
+    let mut user = VirtualUser::new(num_client, &settings, &plan);
+    let mut execution_number: u32 = 0;
+    loop {
+        if level.has_changed().is_err() {
+            break;
+        }
+        let (stage, active) = *level.borrow_and_update();
+        // Only park between iterations, so a multi-step journey is never cut in half.
+        if num_client >= active && user.at_iteration_start() {
+            if level.changed().await.is_err() {
+                break;
+            }
+            continue;
+        }
+
+        let Some(step) = user.next_step(&settings, &plan) else {
+            break;
+        };
+        let mut result = exec(
+            num_client,
+            execution_number,
+            step,
+            &settings,
+            None,
+            &mut user,
+        )
+        .await;
+        result.stage = Some(stage);
+        user.after_step(step, &mut result).await;
+        execution_number += 1;
+        if tx.send(result).await.is_err() {
+            break;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pacer
+// ---------------------------------------------------------------------------
+
+/// Fixed-rate send schedule for a closed-model worker. Send times are derived from the
+/// schedule rather than from the previous response, so a stalled request is followed by
+/// catch-up sends and its delay shows up in the corrected latency.
+struct Pacer {
+    next: Instant,
+    interval: std::time::Duration,
+}
+
+impl Pacer {
+    fn new(interval: std::time::Duration) -> Self {
+        Pacer {
+            next: Instant::now(),
+            interval,
+        }
+    }
+
+    /// Sleeps until the next scheduled send and returns the time it was intended for.
+    async fn wait(&mut self) -> Instant {
+        self.next += self.interval;
+        time::sleep_until(self.next).await;
+        self.next
+    }
+
+    /// Moves the schedule back by a think-time pause, so the pause is neither made up by
+    /// catch-up sends nor counted as send delay in the corrected latency.
+    fn pause(&mut self, think_time_us: u64) {
+        self.next += std::time::Duration::from_micros(think_time_us);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// constant_arrival_rate (open model)
+// ---------------------------------------------------------------------------
+
+/// An iteration scheduled by the arrival-rate generator. Holding `_slot` keeps one of the
+/// `clients` in-flight slots busy until the iteration has been executed.
+struct Arrival {
+    execution: u32,
+    intended: Instant,
+    stage: Option<usize>,
+    _slot: OwnedSemaphorePermit,
+}
+
+/// Starts iterations at a fixed rate independent of response times. `clients` workers pick up
+/// scheduled iterations; when all of them are busy the iteration is reported as dropped instead
+/// of being delayed, so a slow target cannot lower the offered load.
+async fn constant_arrival_rate(
+    settings: Arc<Settings>,
+    plan: Arc<Plan>,
+    tx: Sender<BenchmarkResult>,
+    mut rx_sigint: Option<Receiver<Option<()>>>,
+) {
+    let rps = settings.rps.unwrap_or(1).max(1);
+    let (slots, arrivals_tx, workers) = spawn_arrival_workers(&settings, &plan, &tx);
+
+    let mut ticker = time::interval(std::time::Duration::from_secs_f64(1.0 / rps as f64));
+    // Catch up on late ticks instead of silently lowering the arrival rate.
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+    let begin = Instant::now();
+    let mut execution_number: u32 = 0;
+    loop {
+        match settings.duration {
+            Some(secs) if begin.elapsed().as_secs() >= secs => break,
+            None if execution_number >= settings.requests => break,
+            _ if plan.feeds.exhausted() => break,
+            _ => {}
+        }
+
+        let intended = match &mut rx_sigint {
+            None => ticker.tick().await,
+            Some(rx) => {
+                tokio::select! {
+                    t = ticker.tick() => t,
+                    _ = rx.changed() => break,
+                }
+            }
+        };
+
+        dispatch(&slots, &arrivals_tx, &tx, execution_number, intended, None).await;
+        execution_number += 1;
+    }
+    // Dropping `arrivals_tx` lets the workers exit once the queued iterations are done.
+    drop(arrivals_tx);
+    join_all(workers).await;
+}
+
+/// Runs an `rps` stage profile: like [`constant_arrival_rate`], but the arrival rate follows
+/// the schedule. Every tick starts the iterations that became due since the previous one.
+async fn ramping_arrival_rate(
+    settings: Arc<Settings>,
+    plan: Arc<Plan>,
+    tx: Sender<BenchmarkResult>,
+    mut rx_sigint: Option<Receiver<Option<()>>>,
+    schedule: Schedule,
+) {
+    let (slots, arrivals_tx, workers) = spawn_arrival_workers(&settings, &plan, &tx);
+
+    let mut ticker = time::interval(ARRIVAL_TICK);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+    let begin = Instant::now();
+    let mut execution_number: u32 = 0;
+    loop {
+        let tick = match &mut rx_sigint {
+            None => ticker.tick().await,
+            Some(rx) => {
+                tokio::select! {
+                    t = ticker.tick() => t,
+                    _ = rx.changed() => break,
+                }
+            }
+        };
+        let elapsed = tick.saturating_duration_since(begin);
+        let Some((stage, _)) = schedule.at(elapsed) else {
+            break;
+        };
+        if plan.feeds.exhausted() {
+            break;
+        }
+        let due = schedule.arrivals_until(elapsed) as u64;
+        while (execution_number as u64) < due {
+            dispatch(
+                &slots,
+                &arrivals_tx,
+                &tx,
+                execution_number,
+                tick,
+                Some(stage),
+            )
+            .await;
+            execution_number += 1;
+        }
+    }
+    drop(arrivals_tx);
+    join_all(workers).await;
+}
+
+/// Spawns the `clients` workers executing scheduled arrivals. Returns the in-flight slots, the
+/// queue feeding the workers and the workers themselves.
+fn spawn_arrival_workers(
+    settings: &Arc<Settings>,
+    plan: &Arc<Plan>,
+    tx: &Sender<BenchmarkResult>,
+) -> (
+    Arc<Semaphore>,
+    UnboundedSender<Arrival>,
+    Vec<JoinHandle<()>>,
+) {
+    let slots = Arc::new(Semaphore::new(settings.clients as usize));
+    let (arrivals_tx, arrivals_rx) = mpsc::unbounded_channel::<Arrival>();
+    let arrivals_rx = Arc::new(Mutex::new(arrivals_rx));
+
+    let workers = (0..settings.clients)
+        .map(|id| {
+            tokio::spawn(arrival_worker(
+                id as usize,
+                Arc::clone(settings),
+                Arc::clone(plan),
+                Arc::clone(&arrivals_rx),
+                tx.clone(),
+            ))
+        })
+        .collect();
+    (slots, arrivals_tx, workers)
+}
+
+/// Hands an iteration to an idle worker, or reports it as dropped when every slot is busy.
+async fn dispatch(
+    slots: &Arc<Semaphore>,
+    arrivals: &UnboundedSender<Arrival>,
+    tx: &Sender<BenchmarkResult>,
+    execution: u32,
+    intended: Instant,
+    stage: Option<usize>,
+) {
+    match Arc::clone(slots).try_acquire_owned() {
+        Ok(slot) => {
+            let _ = arrivals.send(Arrival {
+                execution,
+                intended,
+                stage,
+                _slot: slot,
+            });
+        }
+        Err(_) => {
+            let _ = tx
+                .send(BenchmarkResult {
+                    outcome: Outcome::Dropped,
+                    duration_us: 0,
+                    send_delay_us: 0,
+                    num_client: 0,
+                    execution,
+                    timestamp_ms: now_ms(),
+                    phases: Phases::default(),
+                    bytes_received: 0,
+                    bytes_sent: 0,
+                    checks: vec![],
+                    stage,
+                    flow: None,
+                    step: None,
+                    think_time_us: 0,
+                    iteration_us: None,
+                    transaction: None,
+                })
+                .await;
+        }
+    }
+}
+
+async fn arrival_worker(
+    num_client: usize,
+    settings: Arc<Settings>,
+    plan: Arc<Plan>,
+    arrivals: Arc<Mutex<UnboundedReceiver<Arrival>>>,
+    tx: Sender<BenchmarkResult>,
+) {
+    let mut user = VirtualUser::new(num_client, &settings, &plan);
+    loop {
+        let arrival = arrivals.lock().await.recv().await;
+        let Some(arrival) = arrival else {
+            break;
+        };
+        let Some(step) = user.next_step(&settings, &plan) else {
+            break;
+        };
+        let mut result = exec(
+            num_client,
+            arrival.execution,
+            step,
+            &settings,
+            Some(arrival.intended),
+            &mut user,
+        )
+        .await;
+        result.stage = arrival.stage;
+        user.after_step(step, &mut result).await;
+        drop(arrival);
+        let _ = tx.send(result).await;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup and teardown
+// ---------------------------------------------------------------------------
+
+/// Worker id of the virtual user running `setup` and `teardown`, which gives it a random
+/// stream of its own.
+const PHASE_USER: usize = usize::MAX;
+
+/// Runs the `setup` steps once, in order, and returns the values they extracted. The first
+/// step that fails aborts the run.
+async fn run_setup(
+    settings: &Settings,
+    plan: &Plan,
+    steps: &[StepResolved],
+) -> Result<HashMap<String, String>> {
+    let mut user = VirtualUser::new(PHASE_USER, settings, plan);
+    for (i, step) in steps.iter().enumerate() {
+        let result = exec(0, i as u32, step, settings, None, &mut user).await;
+        if let Some(failure) = failure(&result, &step_label(Some("setup"), i)) {
+            anyhow::bail!("Setup failed: {}", failure);
+        }
+    }
+    Ok(user.vars)
+}
+
+/// The `teardown` phase of a run, which starts once the load has ended.
+pub struct Teardown(Option<JoinHandle<Vec<String>>>);
+
+impl Teardown {
+    /// Waits for the teardown steps to finish and returns why each failed one failed. Call it
+    /// once the results channel has closed.
+    pub async fn failures(self) -> Vec<String> {
+        match self.0 {
+            Some(handle) => handle
+                .await
+                .unwrap_or_else(|e| vec![format!("teardown task failed: {}", e)]),
+            None => vec![],
+        }
+    }
+}
+
+/// Waits for the load to end, then runs the `teardown` steps once with the values extracted
+/// by `setup`. A failed step does not stop the remaining ones; the failures are returned.
+/// Holding `tx` keeps the run open until teardown is done.
+async fn run_teardown(
+    settings: Arc<Settings>,
+    plan: Arc<Plan>,
+    steps: Vec<StepResolved>,
+    workers: Vec<JoinHandle<()>>,
+    tx: Sender<BenchmarkResult>,
+) -> Vec<String> {
+    join_all(workers).await;
+    let mut user = VirtualUser::new(PHASE_USER, &settings, &plan);
+    let mut failures = vec![];
+    for (i, step) in steps.iter().enumerate() {
+        let result = exec(0, i as u32, step, &settings, None, &mut user).await;
+        failures.extend(failure(&result, &step_label(Some("teardown"), i)));
+    }
+    drop(tx);
+    failures
+}
+
+/// Why the setup or teardown request of step `label` failed, if it did. Check names already
+/// start with the label.
+fn failure(result: &BenchmarkResult, label: &str) -> Option<String> {
+    if result.is_error() {
+        return Some(format!("{} got '{}'", label, result.outcome));
+    }
+    result
+        .checks
+        .iter()
+        .find(|c| !c.passed)
+        .map(|c| format!("check '{}' did not pass", c.name))
+}
+
+async fn join_all(handles: Vec<JoinHandle<()>>) {
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Single request executor
 // ---------------------------------------------------------------------------
 
+/// Result of a request that could not be built after template substitution.
+fn not_sent(
+    num_client: usize,
+    execution: u32,
+    step: &StepResolved,
+    kind: ErrorKind,
+    message: String,
+) -> BenchmarkResult {
+    BenchmarkResult {
+        outcome: Outcome::Error { kind, message },
+        duration_us: 0,
+        send_delay_us: 0,
+        num_client,
+        execution,
+        timestamp_ms: now_ms(),
+        phases: Phases::default(),
+        bytes_received: 0,
+        bytes_sent: 0,
+        checks: vec![],
+        stage: None,
+        flow: step.flow,
+        step: step.index,
+        think_time_us: 0,
+        iteration_us: None,
+        transaction: None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn exec(
     num_client: usize,
     execution: u32,
-    client: &Client,
     step: &StepResolved,
-    headers_map: &HeaderMap,
-    _settings: &Settings,
+    settings: &Settings,
+    intended: Option<Instant>,
+    user: &mut VirtualUser,
 ) -> BenchmarkResult {
-    // Template substitution on URL and body
-    let url = substitute_variables(step.url.as_str(), execution, num_client);
-    let body = step
-        .body
-        .as_deref()
-        .map(|b| substitute_variables(b, execution, num_client));
+    // Template substitution on URL, headers and body
+    let mut ctx = template::Context {
+        seq: execution,
+        client: num_client,
+        vars: &user.vars,
+        rng: &mut user.rng,
+    };
+    let url = step.url.render(&mut ctx);
+    let body = step.body.as_ref().map(|b| b.render(&mut ctx));
+    let mut headers = step.headers.clone();
+    for (name, template) in &step.header_templates {
+        let value = template.render(&mut ctx);
+        match HeaderValue::from_str(&value) {
+            Ok(v) => {
+                headers.insert(name, v);
+            }
+            Err(_) => {
+                let message = format!("Invalid header value for {}: {:?}", name, value);
+                return not_sent(
+                    num_client,
+                    execution,
+                    step,
+                    ErrorKind::InvalidHeader,
+                    message,
+                );
+            }
+        }
+    }
 
     let parsed_url = match url.parse::<Url>() {
         Ok(u) => u,
         Err(e) => {
-            return BenchmarkResult {
-                status: format!("Invalid URL after substitution: {e}"),
-                duration: 0,
-                num_client,
-                execution,
-                timestamp_ms: now_ms(),
-            };
+            let message = format!("{}: {}", e, url);
+            return not_sent(num_client, execution, step, ErrorKind::InvalidUrl, message);
         }
     };
 
-    let request_builder = match &step.operation {
-        Operation::Get => client.get(parsed_url),
-        Operation::Post => client.post(parsed_url),
-        Operation::Head => client.head(parsed_url),
-        Operation::Patch => client.patch(parsed_url),
-        Operation::Put => client.put(parsed_url),
-        Operation::Delete => client.delete(parsed_url),
-    };
+    let mut request_builder = step.client.request(step.method.clone(), parsed_url.clone());
+    if let Some(timeout) = step.timeout {
+        request_builder = request_builder.timeout(timeout);
+    }
 
+    let bytes_sent = body.as_ref().map_or(0, |b| b.len() as u64);
     let request_builder = match &body {
         None => request_builder,
         Some(b) => request_builder.body(b.clone()),
     };
 
-    // Merge global headers + step-specific headers
-    let mut merged = headers_map.clone();
-    for (k, v) in &step.extra_headers {
-        merged.insert(k, v.clone());
+    if let Some(cookies) = user
+        .cookies
+        .as_mut()
+        .and_then(|jar| jar.header_for(&parsed_url))
+    {
+        // Cookies from the jar are added to any `Cookie` header configured explicitly.
+        let cookies = match headers.get(COOKIE).and_then(|v| v.to_str().ok()) {
+            Some(explicit) => format!("{}; {}", explicit, cookies),
+            None => cookies,
+        };
+        if let Ok(v) = HeaderValue::from_str(&cookies) {
+            headers.insert(COOKIE, v);
+        }
     }
 
     let timestamp_ms = now_ms();
     let begin = Instant::now();
-    let response = request_builder.headers(merged).send().await;
-    let duration_ms = begin.elapsed().as_millis() as u64;
+    let send_delay_us = intended
+        .map(|i| begin.saturating_duration_since(i).as_micros() as u64)
+        .unwrap_or(0);
+    let (response, connect_times) = timing::capture(request_builder.headers(headers).send()).await;
+    let headers_elapsed = begin.elapsed();
+    let failed_phase = connect_times.failed_phase();
+    let mut phases = connect_times.into_phases(headers_elapsed);
 
     match response {
-        Ok(r) => BenchmarkResult {
-            status: r.status().to_string(),
-            duration: duration_ms,
-            num_client,
-            execution,
-            timestamp_ms,
-        },
+        Ok(mut r) => {
+            let status_code = r.status();
+            let mut outcome = Outcome::Status(status_code.as_u16());
+            let headers = std::mem::take(r.headers_mut());
+            if let Some(jar) = &mut user.cookies {
+                jar.store(r.url(), &headers);
+            }
+            // Body checks and extractors need the content; otherwise the body is only counted
+            // and discarded.
+            let keep_body = step.checks.iter().any(|c| c.needs_body())
+                || step.extractors.iter().any(|e| e.needs_body());
+            let mut body = Vec::new();
+            let mut bytes_received = 0;
+            let mut body_ok = true;
+            if settings.read_body || keep_body {
+                loop {
+                    match r.chunk().await {
+                        Ok(Some(chunk)) => {
+                            bytes_received += chunk.len() as u64;
+                            if keep_body {
+                                body.extend_from_slice(&chunk);
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            let kind = match e.is_timeout() {
+                                true => ErrorKind::Timeout,
+                                false => ErrorKind::Body,
+                            };
+                            outcome = Outcome::Error {
+                                kind,
+                                message: error_message(&e),
+                            };
+                            body_ok = false;
+                            break;
+                        }
+                    }
+                }
+                phases.body_us =
+                    Some(begin.elapsed().saturating_sub(headers_elapsed).as_micros() as u64);
+            }
+            let duration_us = begin.elapsed().as_micros() as u64;
+            let checks = if body_ok {
+                let response = checks::Response {
+                    status: status_code.as_u16(),
+                    headers: &headers,
+                    body: &body,
+                    duration_us,
+                };
+                let mut outcomes: Vec<_> =
+                    step.checks.iter().map(|c| c.evaluate(&response)).collect();
+                outcomes.extend(
+                    step.extractors
+                        .iter()
+                        .map(|e| e.apply(&response, &mut user.vars)),
+                );
+                outcomes
+            } else {
+                vec![]
+            };
+            BenchmarkResult {
+                outcome,
+                duration_us,
+                send_delay_us,
+                num_client,
+                execution,
+                timestamp_ms,
+                phases,
+                bytes_received,
+                bytes_sent,
+                checks,
+                stage: None,
+                flow: step.flow,
+                step: step.index,
+                think_time_us: 0,
+                iteration_us: None,
+                transaction: None,
+            }
+        }
         Err(e) => {
-            let status = match e.status() {
-                None => "Failed to connect".to_string(),
-                Some(s) => s.to_string(),
+            let outcome = match e.status() {
+                None => Outcome::Error {
+                    kind: error_kind(&e, failed_phase),
+                    message: error_message(&e),
+                },
+                Some(s) => Outcome::Status(s.as_u16()),
             };
             BenchmarkResult {
-                status,
-                duration: duration_ms,
+                outcome,
+                duration_us: headers_elapsed.as_micros() as u64,
+                send_delay_us,
                 num_client,
                 execution,
                 timestamp_ms,
+                // No response, so there is no first byte to time.
+                phases: Phases {
+                    ttfb_us: None,
+                    ..phases
+                },
+                bytes_received: 0,
+                bytes_sent,
+                checks: vec![],
+                stage: None,
+                flow: step.flow,
+                step: step.index,
+                think_time_us: 0,
+                iteration_us: None,
+                transaction: None,
             }
         }
     }
 }
 
-// ---------------------------------------------------------------------------
-// Variable substitution
-// ---------------------------------------------------------------------------
+/// Classifies a request that got no response, from its error and the phase its connection
+/// attempt stopped in, if any.
+fn error_kind(e: &reqwest::Error, failed_phase: Option<ErrorKind>) -> ErrorKind {
+    if e.is_timeout() {
+        ErrorKind::Timeout
+    } else if let Some(kind) = failed_phase {
+        kind
+    } else if e.is_redirect() {
+        ErrorKind::Redirect
+    } else if e.is_body() || e.is_decode() {
+        ErrorKind::Body
+    } else if is_reset(e) {
+        ErrorKind::Reset
+    } else if e.is_connect() {
+        ErrorKind::Connect
+    } else {
+        ErrorKind::Other
+    }
+}
 
-/// Replace template placeholders in `input`:
-/// - `{{seq}}` → sequential execution number
-/// - `{{client}}` → worker id
-/// - `{{timestamp}}` → Unix timestamp in ms
-/// - `{{uuid}}` → pseudo-random UUID v4
-/// - `{{random_int(min,max)}}` → random integer in [min, max]
-fn substitute_variables(input: &str, seq: u32, client: usize) -> String {
-    if !input.contains("{{") {
-        return input.to_string();
-    }
-
-    let ts = now_ms();
-    let mut result = input
-        .replace("{{seq}}", &seq.to_string())
-        .replace("{{client}}", &client.to_string())
-        .replace("{{timestamp}}", &ts.to_string())
-        .replace("{{uuid}}", &pseudo_uuid(ts, seq, client));
-
-    // Handle {{random_int(min,max)}}
-    let mut out = String::with_capacity(result.len());
-    let bytes = result.as_bytes();
-    let mut i = 0;
-    while i < bytes.len() {
-        if result[i..].starts_with("{{random_int(") {
-            if let Some(end) = result[i..].find(")}}")  {
-                let inner = &result[i + 13..i + end]; // after "{{random_int("
-                let parts: Vec<&str> = inner.splitn(2, ',').collect();
-                if parts.len() == 2 {
-                    if let (Ok(lo), Ok(hi)) =
-                        (parts[0].trim().parse::<i64>(), parts[1].trim().parse::<i64>())
-                    {
-                        let range = (hi - lo).abs() as u64 + 1;
-                        let val = lo + (lcg_rand(ts ^ seq as u64 ^ client as u64) % range) as i64;
-                        out.push_str(&val.to_string());
-                        i += end + 3; // skip "}}"
-                        continue;
-                    }
-                }
+/// Whether the connection was reset or closed by the peer while the request was in flight.
+fn is_reset(e: &reqwest::Error) -> bool {
+    let mut source = e.source();
+    while let Some(err) = source {
+        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
             }
         }
-        out.push(bytes[i] as char);
-        i += 1;
-    }
-
-    if out != result {
-        result = out;
+        // hyper reports a connection closed before the response without an I/O error.
+        if let Some(hyper) = err.downcast_ref::<hyper::Error>() {
+            if hyper.is_closed() || hyper.is_incomplete_message() {
+                return true;
+            }
+        }
+        source = err.source();
     }
-
-    result
-}
-
-/// Very simple LCG random (no external deps, good enough for test data generation).
-fn lcg_rand(seed: u64) -> u64 {
-    seed.wrapping_mul(6_364_136_223_846_793_005)
-        .wrapping_add(1_442_695_040_888_963_407)
+    false
 }
 
-/// Generates a pseudo-UUID v4-shaped string (not cryptographically random).
-fn pseudo_uuid(ts: u64, seq: u32, client: usize) -> String {
-    let a = lcg_rand(ts ^ seq as u64);
-    let b = lcg_rand(a ^ client as u64);
-    format!(
-        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
-        a & 0xFFFF_FFFF,
-        (a >> 32) & 0xFFFF,
-        (a >> 48) & 0xFFF,
-        (b & 0x3FFF) | 0x8000,
-        b >> 16 & 0xFFFF_FFFF_FFFF,
-    )
+/// The error with its chain of causes, which reqwest leaves out of its own message.
+fn error_message(e: &reqwest::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
 }
 
-fn now_ms() -> u64 {
+pub(crate) fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
         .unwrap_or(0)
 }
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draws(seed: Option<u64>, id: usize) -> Vec<u64> {
+        let mut rng = worker_rng(seed, id);
+        (0..4).map(|_| rng.random()).collect()
+    }
+
+    #[test]
+    fn seeded_workers_get_reproducible_independent_streams() {
+        assert_eq!(draws(Some(42), 0), draws(Some(42), 0));
+        assert_ne!(draws(Some(42), 0), draws(Some(42), 1));
+        assert_ne!(draws(Some(42), 0), draws(Some(43), 0));
+        assert_ne!(draws(None, 0), draws(None, 0));
+    }
+
+    #[test]
+    fn steps_with_the_same_protocol_options_share_a_client() {
+        let settings: Settings =
+            serde_yaml::from_str("target: http://localhost:3000/\nclients: 1").unwrap();
+        let mut clients = Clients::default();
+        clients.get(&settings, false, false).unwrap();
+        clients.get(&settings, false, false).unwrap();
+        assert_eq!(clients.built.len(), 1);
+        clients.get(&settings, true, false).unwrap();
+        clients.get(&settings, false, true).unwrap();
+        assert_eq!(clients.built.len(), 3);
+    }
+}
@@ -0,0 +1,226 @@
+//! Response extractors: capture values into per-worker variables for later steps.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::header::{HeaderName, SET_COOKIE};
+use serde_json_path::JsonPath;
+
+use crate::benchmark::CheckOutcome;
+use crate::checks::Response;
+use crate::settings::{ExtractSource, Extractor};
+
+/// Placeholders with a built-in meaning, which extracted variables cannot shadow.
+const RESERVED_NAMES: [&str; 4] = ["seq", "client", "timestamp", "uuid"];
+
+/// An [`Extractor`] with its pattern parsed ahead of time.
+#[derive(Debug)]
+pub(crate) struct CompiledExtractor {
+    name: String,
+    /// Label under which a failed extraction is reported with the checks.
+    label: Arc<str>,
+    source: Source,
+}
+
+#[derive(Debug)]
+enum Source {
+    JsonPath(JsonPath),
+    Regex(Regex),
+    Header(HeaderName),
+    Cookie(String),
+}
+
+impl CompiledExtractor {
+    fn new(extractor: &Extractor, step: &str) -> Result<Self> {
+        let name = extractor.name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!(
+                "Invalid variable name '{}': use letters, digits and '_'",
+                extractor.name
+            );
+        }
+        if RESERVED_NAMES.contains(&name) {
+            anyhow::bail!("'{}' is a built-in variable and cannot be extracted", name);
+        }
+        let source = match &extractor.source {
+            ExtractSource::JsonPath(path) => Source::JsonPath(
+                JsonPath::parse(path).with_context(|| format!("Invalid JSONPath '{}'", path))?,
+            ),
+            ExtractSource::Regex(re) => {
+                Source::Regex(Regex::new(re).with_context(|| format!("Invalid regex '{}'", re))?)
+            }
+            ExtractSource::Header(header) => Source::Header(
+                HeaderName::from_bytes(header.as_bytes())
+                    .with_context(|| format!("Invalid header name '{}'", header))?,
+            ),
+            ExtractSource::Cookie(cookie) => Source::Cookie(cookie.clone()),
+        };
+        Ok(CompiledExtractor {
+            name: name.to_string(),
+            label: format!("{}: extract {}", step, name).into(),
+            source,
+        })
+    }
+
+    pub(crate) fn needs_body(&self) -> bool {
+        matches!(self.source, Source::JsonPath(_) | Source::Regex(_))
+    }
+
+    /// Stores the extracted value in `vars`. When nothing matches, the previous value (if any)
+    /// is kept and the returned outcome is a failure.
+    pub(crate) fn apply(
+        &self,
+        response: &Response,
+        vars: &mut HashMap<String, String>,
+    ) -> CheckOutcome {
+        let value = self.extract(response);
+        let passed = value.is_some();
+        if let Some(value) = value {
+            vars.insert(self.name.clone(), value);
+        }
+        CheckOutcome {
+            name: Arc::clone(&self.label),
+            passed,
+        }
+    }
+
+    fn extract(&self, response: &Response) -> Option<String> {
+        match &self.source {
+            Source::JsonPath(path) => {
+                let json: serde_json::Value = serde_json::from_slice(response.body).ok()?;
+                match path.query(&json).first()? {
+                    serde_json::Value::String(s) => Some(s.clone()),
+                    other => Some(other.to_string()),
+                }
+            }
+            Source::Regex(re) => {
+                let body = String::from_utf8_lossy(response.body);
+                let caps = re.captures(&body)?;
+                caps.get(1)
+                    .or_else(|| caps.get(0))
+                    .map(|m| m.as_str().to_string())
+            }
+            Source::Header(name) => response
+                .headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            Source::Cookie(name) => response
+                .headers
+                .get_all(SET_COOKIE)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .find_map(|v| {
+                    let pair = v.split(';').next()?;
+                    let (k, value) = pair.split_once('=')?;
+                    (k.trim() == name).then(|| value.trim().to_string())
+                }),
+        }
+    }
+}
+
+/// Compiles the extractors of the step labelled `step` (e.g. `step 2`).
+pub(crate) fn compile(extractors: &[Extractor], step: &str) -> Result<Vec<CompiledExtractor>> {
+    extractors
+        .iter()
+        .map(|e| CompiledExtractor::new(e, step))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    fn extractor(source: ExtractSource) -> CompiledExtractor {
+        CompiledExtractor::new(
+            &Extractor {
+                name: "var".to_string(),
+                source,
+            },
+            "step 1",
+        )
+        .unwrap()
+    }
+
+    fn run(source: ExtractSource, headers: &HeaderMap, body: &str) -> Option<String> {
+        let mut vars = HashMap::new();
+        let outcome = extractor(source).apply(
+            &Response {
+                status: 200,
+                headers,
+                body: body.as_bytes(),
+                duration_us: 0,
+            },
+            &mut vars,
+        );
+        assert_eq!(outcome.passed, vars.contains_key("var"));
+        vars.remove("var")
+    }
+
+    #[test]
+    fn json_path_takes_strings_unquoted() {
+        let h = HeaderMap::new();
+        let body = r#"{"token":"abc","id":42}"#;
+        assert_eq!(
+            run(ExtractSource::JsonPath("$.token".into()), &h, body),
+            Some("abc".into())
+        );
+        assert_eq!(
+            run(ExtractSource::JsonPath("$.id".into()), &h, body),
+            Some("42".into())
+        );
+        assert_eq!(
+            run(ExtractSource::JsonPath("$.missing".into()), &h, body),
+            None
+        );
+    }
+
+    #[test]
+    fn regex_prefers_first_capture_group() {
+        let h = HeaderMap::new();
+        let body = "order id=123 created";
+        assert_eq!(
+            run(ExtractSource::Regex(r"id=(\d+)".into()), &h, body),
+            Some("123".into())
+        );
+        assert_eq!(
+            run(ExtractSource::Regex(r"\d+".into()), &h, body),
+            Some("123".into())
+        );
+    }
+
+    #[test]
+    fn header_and_cookie_sources() {
+        let mut h = HeaderMap::new();
+        h.insert("x-request-id", HeaderValue::from_static("r-1"));
+        h.append(SET_COOKIE, HeaderValue::from_static("theme=dark; Path=/"));
+        h.append(SET_COOKIE, HeaderValue::from_static("sid=s3cr3t; HttpOnly"));
+        assert_eq!(
+            run(ExtractSource::Header("X-Request-Id".into()), &h, ""),
+            Some("r-1".into())
+        );
+        assert_eq!(
+            run(ExtractSource::Cookie("sid".into()), &h, ""),
+            Some("s3cr3t".into())
+        );
+        assert_eq!(run(ExtractSource::Cookie("none".into()), &h, ""), None);
+    }
+
+    #[test]
+    fn compile_rejects_bad_names() {
+        let named = |name: &str| Extractor {
+            name: name.to_string(),
+            source: ExtractSource::Header("x".into()),
+        };
+        assert!(compile(&[named("seq")], "step 1").is_err());
+        assert!(compile(&[named("bad name")], "step 1").is_err());
+        assert!(compile(&[named("user_id")], "step 1").is_ok());
+    }
+}
@@ -0,0 +1,283 @@
+//! Data feeders: rows read from CSV or JSONL files, exposed to templates as
+//! `{{feeder.column}}`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use anyhow::{Context, Result};
+use rand::rngs::SmallRng;
+use rand::Rng;
+
+use crate::settings::{Feeder, FeederFormat, FeederStrategy, OnExhaustion, Settings};
+
+/// The scenario's feeders with their rows loaded, shared by all workers.
+#[derive(Debug, Default)]
+pub(crate) struct Feeds {
+    feeds: Vec<Feed>,
+    /// Set once a `stop` feeder has run out of rows.
+    exhausted: AtomicBool,
+}
+
+#[derive(Debug)]
+struct Feed {
+    name: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    strategy: FeederStrategy,
+    on_exhaustion: OnExhaustion,
+    /// Next row of a sequential feeder.
+    cursor: AtomicUsize,
+    /// Number of workers the rows of a unique feeder are dealt out to.
+    workers: usize,
+}
+
+impl Feeds {
+    /// Reads every feeder file of `settings`.
+    pub(crate) fn load(settings: &Settings) -> Result<Self> {
+        let workers = settings.peak_clients() as usize;
+        let feeds = settings
+            .feeders
+            .iter()
+            .map(|feeder| {
+                Feed::load(feeder, workers)
+                    .with_context(|| format!("Cannot load feeder '{}'", feeder.name))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Feeds {
+            feeds,
+            exhausted: AtomicBool::new(false),
+        })
+    }
+
+    /// Number of feeders, i.e. the length of a worker's `draws` counters.
+    pub(crate) fn len(&self) -> usize {
+        self.feeds.len()
+    }
+
+    pub(crate) fn exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+
+    /// Draws the rows of a new iteration of `worker` into `vars`, as `feeder.column`. `draws`
+    /// counts the rows the worker took from each feeder. Returns `false`, without drawing, once
+    /// a `stop` feeder has run out.
+    pub(crate) fn draw(
+        &self,
+        worker: usize,
+        draws: &mut [usize],
+        rng: &mut SmallRng,
+        vars: &mut HashMap<String, String>,
+    ) -> bool {
+        let mut picked = Vec::with_capacity(self.feeds.len());
+        for (feed, count) in self.feeds.iter().zip(draws.iter_mut()) {
+            match feed.pick(worker, *count, rng) {
+                Some(row) => picked.push(row),
+                None => {
+                    self.exhausted.store(true, Ordering::Relaxed);
+                    return false;
+                }
+            }
+            *count += 1;
+        }
+        if self.exhausted() {
+            return false;
+        }
+        for (feed, row) in self.feeds.iter().zip(picked) {
+            for (column, value) in feed.columns.iter().zip(&feed.rows[row]) {
+                vars.insert(format!("{}.{}", feed.name, column), value.clone());
+            }
+        }
+        true
+    }
+}
+
+impl Feed {
+    fn load(feeder: &Feeder, workers: usize) -> Result<Self> {
+        let data = std::fs::read_to_string(&feeder.file)
+            .with_context(|| format!("Cannot read '{}'", feeder.file))?;
+        let (columns, rows) = match feeder.format() {
+            FeederFormat::Csv => parse_csv(&data)?,
+            FeederFormat::Jsonl => parse_jsonl(&data)?,
+        };
+        if rows.is_empty() {
+            anyhow::bail!("'{}' has no rows", feeder.file);
+        }
+        if feeder.strategy == FeederStrategy::Unique && rows.len() < workers {
+            anyhow::bail!(
+                "'{}' has {} rows, fewer than the {} workers of a unique feeder",
+                feeder.file,
+                rows.len(),
+                workers
+            );
+        }
+        Ok(Feed {
+            name: feeder.name.clone(),
+            columns,
+            rows,
+            strategy: feeder.strategy.clone(),
+            on_exhaustion: feeder.on_exhaustion.clone(),
+            cursor: AtomicUsize::new(0),
+            workers: workers.max(1),
+        })
+    }
+
+    /// Index of the row for the `count`-th draw of `worker`, or `None` when a `stop` feeder
+    /// has no row left.
+    fn pick(&self, worker: usize, count: usize, rng: &mut SmallRng) -> Option<usize> {
+        let len = self.rows.len();
+        let recycle = self.on_exhaustion == OnExhaustion::Recycle;
+        match self.strategy {
+            FeederStrategy::Random => Some(rng.random_range(0..len)),
+            FeederStrategy::Sequential => {
+                let next = self.cursor.fetch_add(1, Ordering::Relaxed);
+                (recycle || next < len).then(|| next % len)
+            }
+            FeederStrategy::Unique => {
+                // The worker's rows are worker, worker + workers, worker + 2 * workers, …
+                let own = (len + self.workers - 1 - worker % self.workers) / self.workers;
+                (recycle || count < own).then(|| worker % self.workers + count % own * self.workers)
+            }
+        }
+    }
+}
+
+/// Columns from the header row, then one row per record.
+fn parse_csv(data: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_reader(data.as_bytes());
+    let columns: Vec<String> = reader
+        .headers()
+        .context("Invalid CSV header")?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let rows = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record.with_context(|| format!("Invalid CSV record {}", i + 1))?;
+            Ok(record.iter().map(str::to_string).collect())
+        })
+        .collect::<Result<_>>()?;
+    Ok((columns, rows))
+}
+
+/// One JSON object per non-empty line. Columns are the keys in order of first appearance; a
+/// key missing from a line is empty. Strings are used as is, other values as JSON text.
+fn parse_jsonl(data: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut columns: Vec<String> = vec![];
+    let mut objects = vec![];
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: serde_json::Value = serde_json::from_str(line)
+            .with_context(|| format!("Invalid JSON on line {}", i + 1))?;
+        let serde_json::Value::Object(object) = value else {
+            anyhow::bail!("Line {} is not a JSON object", i + 1);
+        };
+        for key in object.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        objects.push(object);
+    }
+    let rows = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|column| match object.get(column) {
+                    None => String::new(),
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(other) => other.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    Ok((columns, rows))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn feed(strategy: FeederStrategy, on_exhaustion: OnExhaustion, rows: usize) -> Feed {
+        Feed {
+            name: "users".to_string(),
+            columns: vec!["id".to_string()],
+            rows: (0..rows).map(|i| vec![i.to_string()]).collect(),
+            strategy,
+            on_exhaustion,
+            cursor: AtomicUsize::new(0),
+            workers: 2,
+        }
+    }
+
+    fn picks(feed: &Feed, worker: usize, n: usize) -> Vec<Option<usize>> {
+        let mut rng = SmallRng::seed_from_u64(1);
+        (0..n)
+            .map(|count| feed.pick(worker, count, &mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn parses_csv_and_jsonl() {
+        let (columns, rows) = parse_csv("id, name\n1,ana\n2,\"bo, jr\"\n").unwrap();
+        assert_eq!(columns, vec!["id", "name"]);
+        assert_eq!(rows, vec![vec!["1", "ana"], vec!["2", "bo, jr"]]);
+
+        let (columns, rows) =
+            parse_jsonl("{\"id\": 1, \"name\": \"ana\"}\n\n{\"id\": 2, \"tags\": [\"x\"]}\n")
+                .unwrap();
+        assert_eq!(columns, vec!["id", "name", "tags"]);
+        assert_eq!(rows, vec![vec!["1", "ana", ""], vec!["2", "", "[\"x\"]"]]);
+        assert!(parse_jsonl("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn sequential_rows_are_shared_by_workers() {
+        let recycle = feed(FeederStrategy::Sequential, OnExhaustion::Recycle, 3);
+        assert_eq!(picks(&recycle, 0, 2), vec![Some(0), Some(1)]);
+        assert_eq!(picks(&recycle, 1, 2), vec![Some(2), Some(0)]);
+
+        let stop = feed(FeederStrategy::Sequential, OnExhaustion::Stop, 3);
+        assert_eq!(picks(&stop, 0, 4), vec![Some(0), Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn unique_rows_are_dealt_out_to_workers() {
+        let recycle = feed(FeederStrategy::Unique, OnExhaustion::Recycle, 5);
+        assert_eq!(
+            picks(&recycle, 0, 4),
+            vec![Some(0), Some(2), Some(4), Some(0)]
+        );
+        assert_eq!(picks(&recycle, 1, 3), vec![Some(1), Some(3), Some(1)]);
+
+        let stop = feed(FeederStrategy::Unique, OnExhaustion::Stop, 5);
+        assert_eq!(picks(&stop, 1, 3), vec![Some(1), Some(3), None]);
+    }
+
+    #[test]
+    fn draw_fills_variables_until_a_stop_feeder_runs_out() {
+        let feeds = Feeds {
+            feeds: vec![feed(FeederStrategy::Sequential, OnExhaustion::Stop, 2)],
+            exhausted: AtomicBool::new(false),
+        };
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut draws = vec![0];
+        let mut vars = HashMap::new();
+        assert!(feeds.draw(0, &mut draws, &mut rng, &mut vars));
+        assert!(feeds.draw(1, &mut draws, &mut rng, &mut vars));
+        assert_eq!(vars.get("users.id").map(String::as_str), Some("1"));
+        assert!(!feeds.draw(0, &mut draws, &mut rng, &mut vars));
+        assert!(feeds.exhausted());
+    }
+}
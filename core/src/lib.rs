@@ -1,3 +1,11 @@
 pub mod benchmark;
-pub mod settings;
+mod body;
+mod checks;
+mod cookies;
 pub mod execution;
+mod extract;
+mod feeders;
+pub mod settings;
+mod stages;
+mod template;
+mod timing;
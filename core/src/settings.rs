@@ -3,11 +3,14 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::Context;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use strum::EnumString;
 
 use crate::settings::Operation::Get;
+use crate::template::Template;
 
 // ---------------------------------------------------------------------------
 // OutputFormat
@@ -34,6 +37,37 @@ impl std::str::FromStr for OutputFormat {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Executor
+// ---------------------------------------------------------------------------
+
+/// Load model used to schedule requests.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Executor {
+    /// Closed model: each worker sends its next request once the previous one has completed,
+    /// so the offered load drops when the target slows down.
+    #[default]
+    Closed,
+    /// Open model: requests are started at a fixed arrival rate (`rps`) regardless of response
+    /// times. `clients` is the maximum number of requests in flight at any time.
+    ConstantArrivalRate,
+}
+
+impl std::str::FromStr for Executor {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "closed" => Ok(Executor::Closed),
+            "constant-arrival-rate" => Ok(Executor::ConstantArrivalRate),
+            other => anyhow::bail!(
+                "Unknown executor '{}'. Valid options: closed, constant-arrival-rate",
+                other
+            ),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Auth
 // ---------------------------------------------------------------------------
@@ -51,39 +85,13 @@ impl Auth {
         match self {
             Auth::Bearer { token } => format!("Bearer {}", token),
             Auth::Basic { user, password } => {
-                let encoded =
-                    base64_encode(format!("{}:{}", user, password).as_bytes());
+                let encoded = STANDARD.encode(format!("{}:{}", user, password));
                 format!("Basic {}", encoded)
             }
         }
     }
 }
 
-/// Minimal base64 encoder (avoids pulling a full base64 crate for a single use-site).
-fn base64_encode(input: &[u8]) -> String {
-    const TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut out = String::with_capacity((input.len() + 2) / 3 * 4);
-    for chunk in input.chunks(3) {
-        let b0 = chunk[0] as usize;
-        let b1 = chunk.get(1).copied().unwrap_or(0) as usize;
-        let b2 = chunk.get(2).copied().unwrap_or(0) as usize;
-        let n = (b0 << 16) | (b1 << 8) | b2;
-        out.push(TABLE[(n >> 18) & 0x3f] as char);
-        out.push(TABLE[(n >> 12) & 0x3f] as char);
-        if chunk.len() > 1 {
-            out.push(TABLE[(n >> 6) & 0x3f] as char);
-        } else {
-            out.push('=');
-        }
-        if chunk.len() > 2 {
-            out.push(TABLE[n & 0x3f] as char);
-        } else {
-            out.push('=');
-        }
-    }
-    out
-}
-
 // ---------------------------------------------------------------------------
 // Header
 // ---------------------------------------------------------------------------
@@ -112,6 +120,151 @@ pub enum Operation {
     Put,
     #[strum(serialize = "DELETE")]
     Delete,
+    #[strum(serialize = "OPTIONS")]
+    Options,
+    /// Any other method token (`TRACE`, WebDAV's `PROPFIND`, custom verbs…), case preserved.
+    #[strum(default)]
+    Custom(String),
+}
+
+impl Operation {
+    pub fn method(&self) -> reqwest::Method {
+        use reqwest::Method;
+        match self {
+            Operation::Get => Method::GET,
+            Operation::Post => Method::POST,
+            Operation::Head => Method::HEAD,
+            Operation::Patch => Method::PATCH,
+            Operation::Put => Method::PUT,
+            Operation::Delete => Method::DELETE,
+            Operation::Options => Method::OPTIONS,
+            // Checked when parsed.
+            Operation::Custom(name) => Method::from_bytes(name.as_bytes()).unwrap_or_default(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Check — response assertion
+// ---------------------------------------------------------------------------
+
+/// An assertion on a response. A request that fails any of its checks is counted as failed,
+/// separately from HTTP and network errors.
+///
+/// In YAML each check is a single-key map, e.g. `- status: [200, 201]` or
+/// `- json_path: { path: "$.status", equals: "ok" }`.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// Status code is one of these.
+    Status(Vec<u16>),
+    /// Body contains this text.
+    BodyContains(String),
+    /// Body matches this regular expression.
+    BodyMatches(String),
+    /// The first value selected by a JSONPath query equals `equals`.
+    JsonPath {
+        path: String,
+        equals: serde_json::Value,
+    },
+    /// Header is present and, if `matches` is set, its value matches that regular expression.
+    Header {
+        name: String,
+        #[serde(default)]
+        matches: Option<String>,
+    },
+    /// Request latency is at most this many milliseconds.
+    MaxLatencyMs(u64),
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Status(codes) => {
+                let codes: Vec<String> = codes.iter().map(|c| c.to_string()).collect();
+                write!(f, "status in [{}]", codes.join(", "))
+            }
+            Check::BodyContains(text) => write!(f, "body contains {:?}", text),
+            Check::BodyMatches(re) => write!(f, "body matches /{}/", re),
+            Check::JsonPath { path, equals } => write!(f, "{} == {}", path, equals),
+            Check::Header {
+                name,
+                matches: None,
+            } => write!(f, "header {} present", name),
+            Check::Header {
+                name,
+                matches: Some(re),
+            } => write!(f, "header {} matches /{}/", name, re),
+            Check::MaxLatencyMs(ms) => write!(f, "latency <= {}ms", ms),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Extractor — capture a value from a response
+// ---------------------------------------------------------------------------
+
+/// Captures a value from a step's response into a per-worker variable, usable by later
+/// requests of the same worker as `{{name}}`.
+///
+/// ```yaml
+/// extract:
+///   - name: token
+///     json_path: "$.access_token"
+/// ```
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Extractor {
+    /// Variable name.
+    pub name: String,
+    #[serde(flatten)]
+    pub source: ExtractSource,
+}
+
+/// Where an [`Extractor`] reads its value from.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractSource {
+    /// First value selected by a JSONPath query on the body (strings are taken unquoted).
+    JsonPath(String),
+    /// First match of a regular expression on the body; its first capture group if it has one.
+    Regex(String),
+    /// Value of a response header.
+    Header(String),
+    /// Value of a cookie set by the response (`Set-Cookie`).
+    Cookie(String),
+}
+
+// ---------------------------------------------------------------------------
+// ThinkTime — pause between requests of a worker
+// ---------------------------------------------------------------------------
+
+/// Pause a worker takes after a response before its next request, like a user reading a page.
+/// It is not part of the request latency.
+///
+/// ```yaml
+/// think_time: { fixed_ms: 1000 }
+/// think_time: { uniform_ms: { min: 500, max: 3000 } }
+/// think_time: { exponential_ms: 2000 }   # mean; Poisson-distributed user actions
+/// ```
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ThinkTime {
+    /// Always the same pause, in milliseconds.
+    FixedMs(u64),
+    /// Uniformly distributed between `min` and `max` milliseconds.
+    UniformMs { min: u64, max: u64 },
+    /// Exponentially distributed with this mean, in milliseconds.
+    ExponentialMs(u64),
+}
+
+impl std::fmt::Display for ThinkTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThinkTime::FixedMs(ms) => write!(f, "{}ms", ms),
+            ThinkTime::UniformMs { min, max } => write!(f, "{}-{}ms", min, max),
+            ThinkTime::ExponentialMs(mean) => write!(f, "~{}ms (exponential)", mean),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -124,17 +277,372 @@ pub enum Operation {
 pub struct Step {
     /// Same format as `Settings::target`: `[METHOD] <url>`
     pub target: String,
-    pub body: Option<String>,
+    /// Name the step's metrics are reported under. Steps with the same name are reported
+    /// together; see [`Step::metrics_name`].
+    #[serde(default)]
+    pub name: Option<String>,
+    pub body: Option<Payload>,
+    /// Send the contents of this file as is (e.g. an image), instead of `body`.
+    #[serde(default)]
+    pub body_file: Option<String>,
+    /// Send these fields `application/x-www-form-urlencoded`, instead of `body`. A mapping, or
+    /// a sequence of one-field mappings to repeat a name; see [`form_fields`].
+    #[serde(default, with = "form_fields")]
+    pub form: Option<Vec<(String, String)>>,
+    /// Send these parts `multipart/form-data`, instead of `body`.
+    #[serde(default)]
+    pub multipart: Option<Vec<MultipartField>>,
     pub headers: Option<Vec<Header>>,
+    /// Request timeout in milliseconds, overriding the scenario's `timeout`.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Authentication, overriding the scenario's `auth`.
+    #[serde(default)]
+    pub auth: Option<Auth>,
+    /// Use HTTP/2 prior knowledge, overriding the scenario's `http2`.
+    #[serde(default)]
+    pub http2: Option<bool>,
+    /// Accept invalid TLS certificates, overriding the scenario's `insecure`.
+    #[serde(default)]
+    pub insecure: Option<bool>,
+    /// Assertions on this step's response.
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub checks: Vec<Check>,
+    /// Values to capture from this step's response for later steps.
+    #[serde(default)]
+    pub extract: Vec<Extractor>,
+    /// Pause after this step, overriding the scenario's `think_time`.
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub think_time: Option<ThinkTime>,
+    /// Named transaction the step belongs to. Consecutive steps with the same name are timed
+    /// together, from the first request to the end of the last step's think time.
+    #[serde(default)]
+    pub transaction: Option<String>,
 }
 
 impl Step {
-    pub fn operation(&self) -> Operation {
+    pub fn operation(&self) -> anyhow::Result<Operation> {
         parse_operation(&self.target)
     }
     pub fn url(&self) -> String {
         parse_url(&self.target)
     }
+    pub fn request_body(&self) -> anyhow::Result<Option<RequestBody<'_>>> {
+        request_body(&self.body, &self.body_file, &self.form, &self.multipart)
+    }
+
+    /// The step's `name`, or else its method and URL template before substitution
+    /// (`GET http://api/items/{{id}}`), so that requests to the same endpoint are grouped
+    /// whatever values were filled in.
+    pub fn metrics_name(&self) -> String {
+        match (&self.name, self.operation()) {
+            (Some(name), _) => name.clone(),
+            (None, Ok(op)) => format!("{} {}", op.method(), self.url()),
+            (None, Err(_)) => self.target.trim().to_string(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Request bodies
+// ---------------------------------------------------------------------------
+
+/// One part of a `multipart/form-data` body: a text `value` or the contents of a `file`.
+///
+/// ```yaml
+/// multipart:
+///   - name: description
+///     value: "avatar {{seq}}"
+///   - name: avatar
+///     file: ./avatar.png
+///     content_type: image/png
+/// ```
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct MultipartField {
+    pub name: String,
+    /// Text value, templated like any body.
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Path of a file whose contents are sent as is.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// File name sent for a file part. Defaults to the name of `file`.
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Content type of the part. Defaults to `application/octet-stream` for files.
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+/// A `body`: text, or a YAML mapping or sequence sent as JSON.
+///
+/// ```yaml
+/// body:
+///   email: "{{users.email}}"
+///   tags: [load, test]
+/// ```
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Payload {
+    Text(String),
+    /// Kept as YAML so that keys stay in the order they were written.
+    Json(serde_yaml::Value),
+}
+
+/// The body of a request: whichever of `body`, `body_file`, `form` or `multipart` is set.
+#[derive(Clone, PartialEq, Debug)]
+pub enum RequestBody<'a> {
+    /// Text, templated.
+    Text(&'a str),
+    /// JSON; string values are templated.
+    Json(&'a serde_yaml::Value),
+    /// Raw bytes read from a file.
+    File(&'a str),
+    /// `application/x-www-form-urlencoded` fields; values are templated.
+    Form(&'a [(String, String)]),
+    /// `multipart/form-data` parts; text values are templated.
+    Multipart(&'a [MultipartField]),
+}
+
+/// `form` fields, read from a mapping (`user: bob`) or from a sequence of one-field mappings
+/// (`- tag: a`, `- tag: b`) when a name is repeated. Scalar values are taken as strings.
+mod form_fields {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_yaml::{Mapping, Value};
+
+    type Fields = Option<Vec<(String, String)>>;
+
+    pub fn serialize<S: Serializer>(fields: &Fields, serializer: S) -> Result<S::Ok, S::Error> {
+        let pairs = fields.as_ref().map(|fields| {
+            fields
+                .iter()
+                .map(|(name, value)| std::collections::BTreeMap::from([(name, value)]))
+                .collect::<Vec<_>>()
+        });
+        pairs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fields, D::Error> {
+        let mappings: Vec<Mapping> = match Option::<Value>::deserialize(deserializer)? {
+            None => return Ok(None),
+            Some(Value::Mapping(mapping)) => vec![mapping],
+            Some(Value::Sequence(items)) => items
+                .into_iter()
+                .map(|item| match item {
+                    Value::Mapping(mapping) if mapping.len() == 1 => Ok(mapping),
+                    _ => Err(D::Error::custom(
+                        "`form` sequence entries must be single `name: value` mappings",
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => {
+                return Err(D::Error::custom(
+                    "`form` must be a mapping or a sequence of `name: value` mappings",
+                ))
+            }
+        };
+        mappings
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| Ok((scalar(name)?, scalar(value)?)))
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
+    fn scalar<E: Error>(value: Value) -> Result<String, E> {
+        match value {
+            Value::String(text) => Ok(text),
+            Value::Bool(flag) => Ok(flag.to_string()),
+            Value::Number(number) => Ok(number.to_string()),
+            _ => Err(E::custom(
+                "`form` names and values must be strings, numbers or booleans",
+            )),
+        }
+    }
+}
+
+fn request_body<'a>(
+    body: &'a Option<Payload>,
+    body_file: &'a Option<String>,
+    form: &'a Option<Vec<(String, String)>>,
+    multipart: &'a Option<Vec<MultipartField>>,
+) -> anyhow::Result<Option<RequestBody<'a>>> {
+    let bodies: Vec<RequestBody> = [
+        body.as_ref().map(|body| match body {
+            Payload::Text(text) => RequestBody::Text(text),
+            Payload::Json(json) => RequestBody::Json(json),
+        }),
+        body_file.as_deref().map(RequestBody::File),
+        form.as_deref().map(RequestBody::Form),
+        multipart.as_deref().map(RequestBody::Multipart),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if bodies.len() > 1 {
+        anyhow::bail!("Only one of `body`, `body_file`, `form` and `multipart` can be set");
+    }
+    if let Some(RequestBody::Multipart(fields)) = bodies.first() {
+        for field in *fields {
+            if field.name.is_empty() {
+                anyhow::bail!("Every multipart field needs a `name`");
+            }
+            if field.value.is_some() == field.file.is_some() {
+                anyhow::bail!(
+                    "Multipart field '{}' needs exactly one of `value` and `file`",
+                    field.name
+                );
+            }
+        }
+    }
+    Ok(bodies.into_iter().next())
+}
+
+// ---------------------------------------------------------------------------
+// Flow — a named, weighted sequence of steps
+// ---------------------------------------------------------------------------
+
+/// A named sequence of steps. Each worker iteration runs one flow, picked at random in
+/// proportion to the flows' weights.
+///
+/// ```yaml
+/// flows:
+///   - name: browse
+///     weight: 70
+///     steps:
+///       - target: GET http://localhost:3000/products
+/// ```
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Flow {
+    pub name: String,
+    /// Relative share of iterations that run this flow.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    pub steps: Vec<Step>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Label of a scenario step in reports and errors: `step 2`, or `checkout step 2` in a flow.
+pub(crate) fn step_label(flow: Option<&str>, index: usize) -> String {
+    match flow {
+        None => format!("step {}", index + 1),
+        Some(flow) => format!("{} step {}", flow, index + 1),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Feeder — test data read from a file
+// ---------------------------------------------------------------------------
+
+/// A CSV or JSONL file whose rows feed templates: every scenario iteration draws a row and
+/// each of its columns is available as `{{feeder.column}}`.
+///
+/// ```yaml
+/// feeders:
+///   - name: users
+///     file: users.csv
+///     strategy: unique
+///     on_exhaustion: stop
+/// ```
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Feeder {
+    pub name: String,
+    /// Path to the data file, relative to the working directory.
+    pub file: String,
+    /// File format; inferred from the extension when omitted (`.jsonl`/`.ndjson` are JSONL).
+    #[serde(default)]
+    pub format: Option<FeederFormat>,
+    #[serde(default)]
+    pub strategy: FeederStrategy,
+    #[serde(default)]
+    pub on_exhaustion: OnExhaustion,
+}
+
+impl Feeder {
+    pub fn format(&self) -> FeederFormat {
+        self.format.clone().unwrap_or_else(|| {
+            let file = self.file.to_lowercase();
+            if file.ends_with(".jsonl") || file.ends_with(".ndjson") {
+                FeederFormat::Jsonl
+            } else {
+                FeederFormat::Csv
+            }
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FeederFormat {
+    /// Comma-separated values with a header row naming the columns.
+    Csv,
+    /// One JSON object per line; its keys are the columns.
+    Jsonl,
+}
+
+/// How iterations pick their row.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FeederStrategy {
+    /// Rows in file order, shared by all workers: each iteration takes the next row.
+    #[default]
+    Sequential,
+    /// A random row for every iteration.
+    Random,
+    /// Rows dealt out to workers (row `i` goes to worker `i % workers`), so no two workers
+    /// ever use the same row. Each worker reads its rows in order.
+    Unique,
+}
+
+/// What happens once every row has been used.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OnExhaustion {
+    /// Start over from the first row.
+    #[default]
+    Recycle,
+    /// End the test: workers stop at their next iteration.
+    Stop,
+}
+
+// ---------------------------------------------------------------------------
+// Stage — one segment of a load profile
+// ---------------------------------------------------------------------------
+
+/// One segment of a staged load profile. Over `duration` seconds the load moves linearly from
+/// the previous stage's level (0 for the first stage) to this stage's `clients` or `rps`.
+/// A stage with `duration: 0` jumps straight to its level.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Stage {
+    /// Stage length in seconds.
+    pub duration: u64,
+    /// Number of concurrently active workers at the end of the stage.
+    #[serde(default)]
+    pub clients: Option<u32>,
+    /// Arrival rate (requests per second) at the end of the stage.
+    #[serde(default)]
+    pub rps: Option<u32>,
+}
+
+impl Stage {
+    /// Level reached at the end of the stage (clients or rps).
+    pub fn target(&self) -> u32 {
+        self.clients.or(self.rps).unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.clients, self.rps) {
+            (Some(c), _) => write!(f, "{}s to {} clients", self.duration, c),
+            (None, Some(r)) => write!(f, "{}s to {} rps", self.duration, r),
+            (None, None) => write!(f, "{}s", self.duration),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -143,7 +651,9 @@ impl Step {
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Settings {
-    /// Number of concurrent workers.
+    /// Number of concurrent workers (maximum requests in flight with `rps` stages).
+    /// Not needed with `clients` stages, which size the worker pool themselves.
+    #[serde(default)]
     pub clients: u32,
     /// Total number of requests (ignored when `duration` is set).
     #[serde(default)]
@@ -152,7 +662,17 @@ pub struct Settings {
     #[serde(default)]
     pub target: String,
     pub keep_alive: Option<Duration>,
-    pub body: Option<String>,
+    pub body: Option<Payload>,
+    /// Send the contents of this file as is, instead of `body`.
+    #[serde(default)]
+    pub body_file: Option<String>,
+    /// Send these fields `application/x-www-form-urlencoded`, instead of `body`. A mapping, or
+    /// a sequence of one-field mappings to repeat a name; see [`form_fields`].
+    #[serde(default, with = "form_fields")]
+    pub form: Option<Vec<(String, String)>>,
+    /// Send these parts `multipart/form-data`, instead of `body`.
+    #[serde(default)]
+    pub multipart: Option<Vec<MultipartField>>,
     pub headers: Option<Vec<Header>>,
     /// Test duration in seconds (alternative to `requests`).
     pub duration: Option<u64>,
@@ -181,6 +701,16 @@ pub struct Settings {
     /// Sequential steps for multi-target scenarios. When non-empty, `target`/`body`/`headers` are ignored.
     #[serde(default)]
     pub steps: Vec<Step>,
+    /// Weighted flows, an alternative to `steps`: every iteration runs one flow picked by weight.
+    #[serde(default)]
+    pub flows: Vec<Flow>,
+    /// Steps run once before the load starts, unmeasured. Their extracted values are available
+    /// to every worker.
+    #[serde(default)]
+    pub setup: Vec<Step>,
+    /// Steps run once after the load has ended, unmeasured, with the values extracted by `setup`.
+    #[serde(default)]
+    pub teardown: Vec<Step>,
     /// Print live stats every N seconds during the test (0 = disabled).
     #[serde(default)]
     pub live_stats: Option<u64>,
@@ -189,6 +719,37 @@ pub struct Settings {
     /// Disable HTTP keep-alive / connection reuse.
     #[serde(default)]
     pub disable_keepalive: bool,
+    /// Load model: `closed` (default) or `constant-arrival-rate`.
+    #[serde(default)]
+    pub executor: Executor,
+    /// Read every response body to the end (discarding it), so latency covers the full
+    /// download and received bytes are counted.
+    #[serde(default)]
+    pub read_body: bool,
+    /// Assertions on the response of the single target (see `Step::checks` for scenarios).
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub checks: Vec<Check>,
+    /// Give each worker its own cookie jar, kept across its requests.
+    #[serde(default)]
+    pub cookies: bool,
+    /// Clear each worker's cookies and extracted variables at the start of every scenario
+    /// iteration (each pass over the steps), so every iteration starts a fresh session.
+    #[serde(default)]
+    pub reset_session: bool,
+    /// Pause after every request (each step may override it). Closed-model workers only.
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub think_time: Option<ThinkTime>,
+    /// Staged load profile. When set, the stages define the test length and load level instead
+    /// of `requests`/`duration`.
+    #[serde(default)]
+    pub stages: Vec<Stage>,
+    /// Test data files whose rows are available to templates as `{{feeder.column}}`.
+    #[serde(default)]
+    pub feeders: Vec<Feeder>,
+    /// Seed of the workers' random number generators. With a seed, every worker draws the
+    /// same random values (templates, random feeder rows, think times) on every run.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 fn default_timeout() -> Duration {
@@ -213,30 +774,113 @@ impl Settings {
         Ok(settings)
     }
 
+    /// Whether requests follow a rate schedule (`rps` > 0 or `rps` stages).
+    pub fn is_rate_limited(&self) -> bool {
+        self.rps.unwrap_or(0) > 0 || self.has_rps_stages()
+    }
+
+    /// Whether the load profile is made of `rps` stages (open model).
+    pub fn has_rps_stages(&self) -> bool {
+        self.stages.first().is_some_and(|s| s.rps.is_some())
+    }
+
+    /// Largest number of workers the test runs at once.
+    pub fn peak_clients(&self) -> u32 {
+        if self.stages.is_empty() || self.has_rps_stages() {
+            self.clients
+        } else {
+            self.stages.iter().map(Stage::target).max().unwrap_or(0)
+        }
+    }
+
+    /// Test length in seconds, when the test is time-based (`duration` or `stages`).
+    pub fn total_duration(&self) -> Option<u64> {
+        if self.stages.is_empty() {
+            self.duration
+        } else {
+            Some(self.stages.iter().map(|s| s.duration).sum())
+        }
+    }
+
     /// HTTP operation derived from the primary `target` string.
-    pub fn operation(&self) -> Operation {
+    pub fn operation(&self) -> anyhow::Result<Operation> {
         parse_operation(&self.target)
     }
 
+    pub fn request_body(&self) -> anyhow::Result<Option<RequestBody<'_>>> {
+        request_body(&self.body, &self.body_file, &self.form, &self.multipart)
+    }
+
     /// URL derived from the primary `target` string.
     pub fn target_url(&self) -> String {
         parse_url(&self.target)
     }
 
+    /// Whether requests come from `steps` or `flows` rather than the single `target`.
+    pub fn is_scenario(&self) -> bool {
+        !self.steps.is_empty() || !self.flows.is_empty()
+    }
+
+    /// Scenario steps grouped by flow: the `flows`, or `steps` as a single unnamed flow.
+    pub fn step_groups(&self) -> Vec<(Option<&str>, &[Step])> {
+        if self.flows.is_empty() {
+            vec![(None, self.steps.as_slice())]
+        } else {
+            self.flows
+                .iter()
+                .map(|f| (Some(f.name.as_str()), f.steps.as_slice()))
+                .collect()
+        }
+    }
+
+    /// Every step of the scenario, across flows.
+    pub(crate) fn all_steps(&self) -> impl Iterator<Item = &Step> {
+        self.steps
+            .iter()
+            .chain(self.flows.iter().flat_map(|f| f.steps.iter()))
+    }
+
+    /// The distinct [`Step::metrics_name`]s of the scenario steps, in the order they first
+    /// appear, each with the first step reported under it.
+    pub fn step_metrics(&self) -> Vec<(String, &Step)> {
+        let mut metrics: Vec<(String, &Step)> = vec![];
+        for step in self.all_steps() {
+            let name = step.metrics_name();
+            if !metrics.iter().any(|(n, _)| *n == name) {
+                metrics.push((name, step));
+            }
+        }
+        metrics
+    }
+
+    /// Names of the scenario's transactions, in the order they first appear. Flows may share a
+    /// transaction name; their durations are then reported together.
+    pub fn transactions(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for name in self.all_steps().filter_map(|s| s.transaction.as_deref()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
     /// Validate settings before starting the benchmark.
     pub fn validate(&self) -> anyhow::Result<()> {
-        if self.clients == 0 {
+        if !self.stages.is_empty() {
+            self.validate_stages()?;
+        } else if self.clients == 0 {
             anyhow::bail!("--clients must be greater than 0");
-        }
-        if self.duration.is_none() && self.requests == 0 {
+        } else if self.duration.is_none() && self.requests == 0 {
             anyhow::bail!("--iterations must be greater than 0");
         }
 
         // Validate primary target URL (unless using multi-step scenario)
-        if self.steps.is_empty() {
+        if !self.is_scenario() {
             if self.target.trim().is_empty() {
                 anyhow::bail!("--target cannot be empty");
             }
+            self.operation()?;
             let url_str = self.target_url();
             let parsed = Url::parse(&url_str).with_context(|| {
                 format!(
@@ -248,28 +892,74 @@ impl Settings {
             if scheme != "http" && scheme != "https" {
                 anyhow::bail!(
                     "Invalid URL scheme '{}' in '{}'. Only http and https are supported.",
-                    scheme, url_str
+                    scheme,
+                    url_str
                 );
             }
         } else {
-            // Validate each step's URL
-            for (i, step) in self.steps.iter().enumerate() {
-                let url_str = step.url();
-                let parsed = Url::parse(&url_str).with_context(|| {
-                    format!(
-                        "Invalid URL '{}' in step {} of scenario. Make sure to include the scheme (http:// or https://)",
-                        url_str, i + 1
-                    )
-                })?;
-                let scheme = parsed.scheme();
-                if scheme != "http" && scheme != "https" {
+            if !self.flows.is_empty() {
+                self.validate_flows()?;
+            }
+            for (flow, steps) in self.step_groups() {
+                for (i, step) in steps.iter().enumerate() {
+                    validate_step(step, &step_label(flow, i))?;
+                }
+                validate_transactions(steps, flow)?;
+            }
+        }
+        for (phase, steps) in [("setup", &self.setup), ("teardown", &self.teardown)] {
+            for (i, step) in steps.iter().enumerate() {
+                validate_step(step, &step_label(Some(phase), i))?;
+                if step.transaction.is_some() {
+                    anyhow::bail!(
+                        "`transaction` is not supported in {}",
+                        step_label(Some(phase), i)
+                    );
+                }
+            }
+        }
+
+        for header in self.headers.iter().flatten() {
+            Template::parse(&header.value)
+                .with_context(|| format!("Invalid template in header '{}'", header.key))?;
+        }
+        if !self.is_scenario() {
+            validate_templates(&self.target_url(), self.request_body()?, None)?;
+            crate::checks::compile(&self.checks, None).context("Invalid check")?;
+        }
+
+        let think_times: Vec<&ThinkTime> = self
+            .think_time
+            .iter()
+            .chain(self.all_steps().filter_map(|s| s.think_time.as_ref()))
+            .collect();
+        for think_time in &think_times {
+            if let ThinkTime::UniformMs { min, max } = think_time {
+                if min > max {
                     anyhow::bail!(
-                        "Invalid URL scheme '{}' in step {}. Only http and https are supported.",
-                        scheme, i + 1
+                        "think_time: uniform `min` ({}) is greater than `max` ({})",
+                        min,
+                        max
                     );
                 }
             }
         }
+        if !think_times.is_empty()
+            && (self.executor == Executor::ConstantArrivalRate || self.has_rps_stages())
+        {
+            anyhow::bail!(
+                "think_time only applies to closed-model workers; with an arrival rate the schedule sets the pacing"
+            );
+        }
+
+        if self.executor == Executor::ConstantArrivalRate && self.stages.is_empty() {
+            if self.rps.unwrap_or(0) == 0 {
+                anyhow::bail!("--executor constant-arrival-rate requires --rps greater than 0");
+            }
+            if self.ramp_up.is_some() {
+                anyhow::bail!("--ramp-up is not supported by the constant-arrival-rate executor");
+            }
+        }
 
         if let Some(ramp_up) = self.ramp_up {
             if let Some(dur) = self.duration {
@@ -280,6 +970,78 @@ impl Settings {
                 }
             }
         }
+
+        for (i, feeder) in self.feeders.iter().enumerate() {
+            let name = &feeder.name;
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                anyhow::bail!(
+                    "Invalid feeder name '{}': use letters, digits and '_'",
+                    name
+                );
+            }
+            if self.feeders[..i].iter().any(|f| f.name == *name) {
+                anyhow::bail!("Duplicate feeder name '{}'", name);
+            }
+            if feeder.file.trim().is_empty() {
+                anyhow::bail!("Feeder '{}' needs a `file`", name);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_flows(&self) -> anyhow::Result<()> {
+        if !self.steps.is_empty() {
+            anyhow::bail!("A scenario can define `steps` or `flows`, not both");
+        }
+        for (i, flow) in self.flows.iter().enumerate() {
+            if flow.name.trim().is_empty() {
+                anyhow::bail!("Flow {} needs a `name`", i + 1);
+            }
+            if self.flows[..i].iter().any(|f| f.name == flow.name) {
+                anyhow::bail!("Duplicate flow name '{}'", flow.name);
+            }
+            if flow.weight == 0 {
+                anyhow::bail!("Flow '{}': `weight` must be greater than 0", flow.name);
+            }
+            if flow.steps.is_empty() {
+                anyhow::bail!("Flow '{}' has no steps", flow.name);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_stages(&self) -> anyhow::Result<()> {
+        let rps_stages = self.has_rps_stages();
+        for (i, stage) in self.stages.iter().enumerate() {
+            match (stage.clients, stage.rps) {
+                (Some(_), Some(_)) | (None, None) => {
+                    anyhow::bail!("Stage {} must set exactly one of `clients` or `rps`", i + 1)
+                }
+                (_, rps) if rps.is_some() != rps_stages => anyhow::bail!(
+                    "Stage {}: all stages must use the same kind of target (`clients` or `rps`)",
+                    i + 1
+                ),
+                _ => {}
+            }
+        }
+        if self.total_duration() == Some(0) {
+            anyhow::bail!("Stages must last longer than 0 seconds in total");
+        }
+        if self.peak_clients() == 0 {
+            anyhow::bail!(if rps_stages {
+                "`rps` stages need `clients` (maximum requests in flight) greater than 0"
+            } else {
+                "At least one stage must have `clients` greater than 0"
+            });
+        }
+        if self.duration.is_some() || self.ramp_up.is_some() || self.rps.is_some() {
+            anyhow::bail!("`duration`, `ramp_up` and `rps` cannot be combined with `stages`");
+        }
+        if !rps_stages && self.executor == Executor::ConstantArrivalRate {
+            anyhow::bail!(
+                "`clients` stages run closed-model workers; use `rps` stages for an arrival rate"
+            );
+        }
         Ok(())
     }
 }
@@ -288,38 +1050,140 @@ impl Settings {
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn parse_operation(target: &str) -> Operation {
-    let slices: Vec<&str> = target.split_whitespace().collect();
-    if slices.len() == 1 {
-        return Get;
+/// Splits `[METHOD] <url>` into its method, if any, and URL. The URL may contain spaces (e.g.
+/// between template function arguments).
+fn split_target(target: &str) -> (Option<&str>, &str) {
+    let target = target.trim();
+    match target.split_once(char::is_whitespace) {
+        Some((method, url)) if !method.contains("://") => (Some(method), url.trim()),
+        _ => (None, target),
     }
-    match slices.first() {
-        None => Get,
-        Some(op) => match Operation::from_str(&op.to_uppercase()) {
-            Ok(o) => o,
-            Err(_) => {
-                eprintln!(
-                    "Warning: unknown HTTP method '{}', defaulting to GET",
-                    op
-                );
-                Get
+}
+
+/// The method of `target`: a standard one in any case, or any other valid method token as is.
+fn parse_operation(target: &str) -> anyhow::Result<Operation> {
+    let Some(op) = split_target(target).0 else {
+        return Ok(Get);
+    };
+    match Operation::from_str(&op.to_uppercase()) {
+        Ok(Operation::Custom(_)) | Err(_) => {
+            reqwest::Method::from_bytes(op.as_bytes())
+                .with_context(|| format!("Invalid HTTP method '{}'", op))?;
+            Ok(Operation::Custom(op.to_string()))
+        }
+        Ok(o) => Ok(o),
+    }
+}
+
+/// Checks the transaction names of one flow's steps: valid names, and the steps of each
+/// transaction next to each other.
+fn validate_transactions(steps: &[Step], flow: Option<&str>) -> anyhow::Result<()> {
+    for (i, step) in steps.iter().enumerate() {
+        let Some(name) = step.transaction.as_deref() else {
+            continue;
+        };
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            anyhow::bail!(
+                "Invalid transaction name '{}' in {}: use letters, digits, '_' and '-'",
+                name,
+                step_label(flow, i)
+            );
+        }
+        let continues = i > 0 && steps[i - 1].transaction.as_deref() == Some(name);
+        if !continues
+            && steps[..i]
+                .iter()
+                .any(|s| s.transaction.as_deref() == Some(name))
+        {
+            anyhow::bail!(
+                "The steps of transaction '{}' must be consecutive ({})",
+                name,
+                step_label(flow, i)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks a scenario step: its target, options, templates, checks and extractors.
+fn validate_step(step: &Step, label: &str) -> anyhow::Result<()> {
+    step.operation()
+        .with_context(|| format!("Invalid target in {}", label))?;
+    if step
+        .name
+        .as_deref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        anyhow::bail!("`name` cannot be empty in {}", label);
+    }
+    if step.timeout_ms == Some(0) {
+        anyhow::bail!("timeout_ms must be greater than 0 in {}", label);
+    }
+    let url_str = step.url();
+    let parsed = Url::parse(&url_str).with_context(|| {
+        format!(
+            "Invalid URL '{}' in {} of scenario. Make sure to include the scheme (http:// or https://)",
+            url_str, label
+        )
+    })?;
+    let scheme = parsed.scheme();
+    if scheme != "http" && scheme != "https" {
+        anyhow::bail!(
+            "Invalid URL scheme '{}' in {}. Only http and https are supported.",
+            scheme,
+            label
+        );
+    }
+    let body = step
+        .request_body()
+        .with_context(|| format!("Invalid body in {}", label))?;
+    validate_templates(&url_str, body, step.headers.as_deref())
+        .with_context(|| format!("Invalid template in {}", label))?;
+    crate::checks::compile(&step.checks, Some(label))
+        .with_context(|| format!("Invalid check in {}", label))?;
+    crate::extract::compile(&step.extract, label)
+        .with_context(|| format!("Invalid extractor in {}", label))?;
+    Ok(())
+}
+
+/// Parses the templates of a request, so template errors surface before the run starts.
+fn validate_templates(
+    url: &str,
+    body: Option<RequestBody>,
+    headers: Option<&[Header]>,
+) -> anyhow::Result<()> {
+    Template::parse(url)?;
+    match body {
+        Some(RequestBody::Text(text)) => {
+            Template::parse(text)?;
+        }
+        Some(RequestBody::Json(json)) => {
+            crate::body::JsonTemplate::parse(json)?;
+        }
+        Some(RequestBody::Form(fields)) => {
+            for (_, value) in fields.iter() {
+                Template::parse(value)?;
             }
-        },
+        }
+        Some(RequestBody::Multipart(fields)) => {
+            for value in fields.iter().filter_map(|f| f.value.as_deref()) {
+                Template::parse(value)?;
+            }
+        }
+        Some(RequestBody::File(_)) | None => {}
     }
+    for header in headers.unwrap_or_default() {
+        Template::parse(&header.value)?;
+    }
+    Ok(())
 }
 
 fn parse_url(target: &str) -> String {
-    let slices: Vec<&str> = target.split_whitespace().collect();
-    if slices.len() == 1 {
-        return slices
-            .first()
-            .expect("target is not well formatted")
-            .to_string();
-    }
-    slices
-        .get(1)
-        .expect("target is not well formatted")
-        .to_string()
+    split_target(target).1.to_string()
 }
 
 // ---------------------------------------------------------------------------
@@ -338,6 +1202,9 @@ mod tests {
             target: "GET http://localhost:3000/".to_string(),
             keep_alive: None,
             body: None,
+            body_file: None,
+            form: None,
+            multipart: None,
             headers: None,
             duration: None,
             verbose: false,
@@ -354,6 +1221,18 @@ mod tests {
             live_stats: None,
             pool_idle_timeout: None,
             disable_keepalive: false,
+            executor: Executor::Closed,
+            read_body: false,
+            checks: vec![],
+            cookies: false,
+            reset_session: false,
+            stages: vec![],
+            think_time: None,
+            flows: vec![],
+            setup: vec![],
+            teardown: vec![],
+            feeders: vec![],
+            seed: None,
         }
     }
 
@@ -365,7 +1244,7 @@ mod tests {
             target: "http://localhost:3000/".to_string(),
             ..base_settings()
         };
-        assert_eq!(s.operation(), Operation::Get);
+        assert_eq!(s.operation().unwrap(), Operation::Get);
     }
 
     #[test]
@@ -374,7 +1253,7 @@ mod tests {
             target: "POST http://localhost:3000/".to_string(),
             ..base_settings()
         };
-        assert_eq!(s.operation(), Operation::Post);
+        assert_eq!(s.operation().unwrap(), Operation::Post);
     }
 
     #[test]
@@ -385,16 +1264,36 @@ mod tests {
             target: "put http://localhost:3000/".to_string(),
             ..base_settings()
         };
-        assert_eq!(s.operation(), Operation::Put);
+        assert_eq!(s.operation().unwrap(), Operation::Put);
     }
 
     #[test]
-    fn operation_falls_back_to_get_for_unknown_method() {
+    fn operation_keeps_other_method_tokens() {
         let s = Settings {
-            target: "FOOBAR http://localhost:3000/".to_string(),
+            target: "options http://localhost:3000/".to_string(),
             ..base_settings()
         };
-        assert_eq!(s.operation(), Operation::Get);
+        assert_eq!(s.operation().unwrap(), Operation::Options);
+        let s = Settings {
+            target: "PROPFIND http://localhost:3000/".to_string(),
+            ..base_settings()
+        };
+        assert_eq!(
+            s.operation().unwrap(),
+            Operation::Custom("PROPFIND".to_string())
+        );
+        assert_eq!(s.operation().unwrap().method().as_str(), "PROPFIND");
+        assert!(s.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_invalid_method() {
+        let s = Settings {
+            target: "GE(T http://localhost:3000/".to_string(),
+            ..base_settings()
+        };
+        assert!(s.operation().is_err());
+        assert!(s.validate().is_err());
     }
 
     // --- target_url() ---
@@ -417,6 +1316,276 @@ mod tests {
         assert_eq!(s.target_url(), "http://example.com/api");
     }
 
+    #[test]
+    fn target_url_keeps_spaces_inside_templates() {
+        let s = Settings {
+            target: "PUT http://example.com/{{random_choice('a', 'b')}}".to_string(),
+            ..base_settings()
+        };
+        assert_eq!(s.operation().unwrap(), Operation::Put);
+        assert_eq!(
+            s.target_url(),
+            "http://example.com/{{random_choice('a', 'b')}}"
+        );
+        assert!(s.validate().is_ok());
+
+        let s = Settings {
+            target: "http://example.com/{{random_int(1, 5)}}".to_string(),
+            ..base_settings()
+        };
+        assert_eq!(s.operation().unwrap(), Operation::Get);
+        assert_eq!(s.target_url(), "http://example.com/{{random_int(1, 5)}}");
+    }
+
+    #[test]
+    fn validate_rejects_invalid_templates() {
+        let s = Settings {
+            target: "http://example.com/{{random_int(1)}}".to_string(),
+            ..base_settings()
+        };
+        assert!(s.validate().is_err());
+        let s = Settings {
+            body: Some(Payload::Text("{{nope()}}".to_string())),
+            ..base_settings()
+        };
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn structured_bodies_parse_from_yaml_and_validate() {
+        let yaml = r#"
+clients: 1
+requests: 1
+steps:
+  - target: POST http://localhost:3000/login
+    form:
+      user: "{{users.name}}"
+      remember: true
+  - target: POST http://localhost:3000/upload
+    multipart:
+      - name: title
+        value: "photo {{seq}}"
+      - name: photo
+        file: ./photo.png
+        content_type: image/png
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        s.validate().unwrap();
+        let Some(RequestBody::Form(form)) = s.steps[0].request_body().unwrap() else {
+            panic!("expected a form body");
+        };
+        assert_eq!(form[1], ("remember".to_string(), "true".to_string()));
+        assert!(matches!(
+            s.steps[1].request_body().unwrap(),
+            Some(RequestBody::Multipart(fields)) if fields.len() == 2
+        ));
+
+        let s = Settings {
+            body: Some(Payload::Text("{}".to_string())),
+            body_file: Some("photo.png".to_string()),
+            ..base_settings()
+        };
+        assert!(s.validate().is_err());
+        let s = Settings {
+            multipart: Some(vec![MultipartField {
+                name: "photo".to_string(),
+                value: Some("x".to_string()),
+                file: Some("photo.png".to_string()),
+                filename: None,
+                content_type: None,
+            }]),
+            ..base_settings()
+        };
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn form_keeps_repeated_names_in_order() {
+        let yaml = r#"
+clients: 1
+requests: 1
+target: POST http://localhost:3000/search
+form:
+  - tag: a
+  - q: rust
+  - tag: b
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        let expected = vec![
+            ("tag".to_string(), "a".to_string()),
+            ("q".to_string(), "rust".to_string()),
+            ("tag".to_string(), "b".to_string()),
+        ];
+        assert_eq!(s.form, Some(expected));
+
+        let yaml = serde_yaml::to_string(&s).unwrap();
+        let round_trip: Settings = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(round_trip.form, s.form);
+
+        let yaml = "clients: 1\nrequests: 1\nform:\n  - tag: a\n    q: rust\n";
+        assert!(serde_yaml::from_str::<Settings>(yaml).is_err());
+    }
+
+    #[test]
+    fn body_accepts_text_or_structured_json() {
+        let yaml = r#"
+clients: 1
+requests: 1
+body: '{"raw": true}'
+steps:
+  - target: POST http://localhost:3000/orders
+    body:
+      item: "{{random_choice('a', 'b')}}"
+      qty: 2
+  - target: POST http://localhost:3000/bulk
+    body: [1, 2]
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        s.validate().unwrap();
+        assert_eq!(s.body, Some(Payload::Text(r#"{"raw": true}"#.to_string())));
+        assert!(
+            matches!(s.steps[0].request_body().unwrap(), Some(RequestBody::Json(v)) if v.is_mapping())
+        );
+        assert!(
+            matches!(s.steps[1].request_body().unwrap(), Some(RequestBody::Json(v)) if v.is_sequence())
+        );
+
+        let yaml = "target: POST http://localhost:3000/\nclients: 1\nrequests: 1\nbody:\n  item: '{{nope()}}'\n";
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn steps_override_timeout_auth_and_protocol() {
+        let yaml = r#"
+clients: 1
+requests: 1
+steps:
+  - target: GET https://localhost:3000/reports
+    timeout_ms: 120000
+    http2: true
+    insecure: true
+    auth:
+      type: basic
+      user: report
+      password: secret
+  - target: GET http://localhost:3000/api
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        s.validate().unwrap();
+        assert_eq!(s.steps[0].timeout_ms, Some(120_000));
+        assert_eq!(s.steps[0].http2, Some(true));
+        assert_eq!(s.steps[0].insecure, Some(true));
+        assert!(matches!(&s.steps[0].auth, Some(Auth::Basic { user, .. }) if user == "report"));
+        assert_eq!(s.steps[1].timeout_ms, None);
+        assert_eq!(s.steps[1].auth, None);
+
+        let mut s = s;
+        s.steps[1].timeout_ms = Some(0);
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn setup_and_teardown_steps_are_validated() {
+        let yaml = r#"
+clients: 1
+requests: 1
+target: http://localhost:3000/items/{{tenant}}
+setup:
+  - target: POST http://localhost:3000/tenants
+    extract:
+      - name: tenant
+        json_path: "$.id"
+teardown:
+  - target: DELETE http://localhost:3000/tenants/{{tenant}}
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        s.validate().unwrap();
+        assert_eq!(s.setup.len(), 1);
+        assert_eq!(s.teardown.len(), 1);
+
+        let mut bad = s.clone();
+        bad.setup[0].target = "POST localhost/tenants".to_string();
+        assert!(bad
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("setup step 1"));
+        let mut bad = s;
+        bad.teardown[0].target = "FOO BAR http://localhost".to_string();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn transactions_group_consecutive_steps() {
+        let yaml = r#"
+clients: 1
+requests: 1
+flows:
+  - name: buy
+    steps:
+      - target: GET http://localhost:3000/cart
+        transaction: checkout
+      - target: POST http://localhost:3000/orders
+        transaction: checkout
+      - target: GET http://localhost:3000/orders/1
+  - name: login
+    steps:
+      - target: POST http://localhost:3000/login
+        transaction: sign-in
+      - target: GET http://localhost:3000/cart
+        transaction: checkout
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        s.validate().unwrap();
+        assert_eq!(s.transactions(), vec!["checkout", "sign-in"]);
+
+        let mut bad = s.clone();
+        bad.flows[0].steps[2].transaction = Some("checkout".to_string());
+        bad.flows[0].steps[1].transaction = None;
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("must be consecutive"), "{err}");
+
+        let mut bad = s.clone();
+        bad.flows[1].steps[0].transaction = Some("sign in".to_string());
+        assert!(bad.validate().is_err());
+
+        let mut bad = s;
+        bad.setup = vec![bad.flows[0].steps[0].clone()];
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn step_metrics_group_by_name_or_url_template() {
+        let yaml = r#"
+clients: 1
+requests: 1
+flows:
+  - name: browse
+    steps:
+      - target: http://localhost:3000/items/{{seq}}
+      - target: post http://localhost:3000/cart
+        name: add-to-cart
+  - name: buy
+    steps:
+      - target: GET http://localhost:3000/items/{{seq}}
+      - target: POST http://localhost:3000/cart/{{client}}
+        name: add-to-cart
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        s.validate().unwrap();
+        let names: Vec<String> = s.step_metrics().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            vec!["GET http://localhost:3000/items/{{seq}}", "add-to-cart"]
+        );
+        assert_eq!(s.flows[0].steps[1].metrics_name(), "add-to-cart");
+
+        let mut bad = s;
+        bad.flows[1].steps[0].name = Some(" ".to_string());
+        assert!(bad.validate().is_err());
+    }
+
     // --- requests_by_client() ---
 
     #[test]
@@ -514,6 +1683,43 @@ mod tests {
         assert!(base_settings().validate().is_ok());
     }
 
+    #[test]
+    fn validate_rejects_arrival_rate_without_rps() {
+        let s = Settings {
+            executor: Executor::ConstantArrivalRate,
+            rps: None,
+            ..base_settings()
+        };
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn validate_accepts_arrival_rate_with_rps() {
+        let s = Settings {
+            executor: Executor::ConstantArrivalRate,
+            rps: Some(100),
+            ..base_settings()
+        };
+        assert!(s.validate().is_ok());
+    }
+
+    // --- Executor ---
+
+    #[test]
+    fn executor_parses_known_values() {
+        assert_eq!("closed".parse::<Executor>().unwrap(), Executor::Closed);
+        assert_eq!(
+            "constant-arrival-rate".parse::<Executor>().unwrap(),
+            Executor::ConstantArrivalRate
+        );
+    }
+
+    #[test]
+    fn executor_rejects_unknown() {
+        assert!("ramping".parse::<Executor>().is_err());
+        assert!("open".parse::<Executor>().is_err());
+    }
+
     // --- OutputFormat ---
 
     #[test]
@@ -556,4 +1762,190 @@ mod tests {
         // base64("user:pass") = "dXNlcjpwYXNz"
         assert_eq!(auth.header_value(), "Basic dXNlcjpwYXNz");
     }
+
+    // --- Checks ---
+
+    #[test]
+    fn checks_parse_from_yaml() {
+        let yaml = r#"
+clients: 1
+requests: 1
+target: http://localhost:3000
+checks:
+  - status: [200, 201]
+  - body_contains: ok
+  - json_path: { path: "$.id", equals: 42 }
+  - header: { name: content-type }
+  - max_latency_ms: 500
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(s.checks.len(), 5);
+        assert_eq!(s.checks[0], Check::Status(vec![200, 201]));
+        assert_eq!(s.checks[2].to_string(), "$.id == 42");
+        assert_eq!(s.checks[3].to_string(), "header content-type present");
+        assert!(s.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_invalid_check_regex() {
+        let s = Settings {
+            checks: vec![Check::BodyMatches("[".to_string())],
+            ..base_settings()
+        };
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn stages_parse_from_yaml_and_size_the_run() {
+        let yaml = r#"
+target: http://localhost:3000
+stages:
+  - { duration: 30, clients: 50 }
+  - { duration: 60, clients: 50 }
+  - { duration: 10, clients: 0 }
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert!(s.validate().is_ok());
+        assert_eq!(s.peak_clients(), 50);
+        assert_eq!(s.total_duration(), Some(100));
+        assert!(!s.is_rate_limited());
+        assert_eq!(s.stages[0].to_string(), "30s to 50 clients");
+    }
+
+    #[test]
+    fn think_time_parses_per_scenario_and_step() {
+        let yaml = r#"
+clients: 1
+requests: 2
+think_time: { uniform_ms: { min: 500, max: 1500 } }
+steps:
+  - target: http://localhost:3000/a
+    think_time: { exponential_ms: 2000 }
+  - target: http://localhost:3000/b
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            s.think_time,
+            Some(ThinkTime::UniformMs {
+                min: 500,
+                max: 1500
+            })
+        );
+        assert_eq!(s.steps[0].think_time, Some(ThinkTime::ExponentialMs(2000)));
+        assert_eq!(s.steps[1].think_time, None);
+        assert!(s.validate().is_ok());
+
+        let inverted = Settings {
+            think_time: Some(ThinkTime::UniformMs { min: 10, max: 5 }),
+            ..base_settings()
+        };
+        assert!(inverted.validate().is_err());
+        let open_model = Settings {
+            think_time: Some(ThinkTime::FixedMs(100)),
+            executor: Executor::ConstantArrivalRate,
+            rps: Some(10),
+            ..base_settings()
+        };
+        assert!(open_model.validate().is_err());
+    }
+
+    #[test]
+    fn flows_parse_from_yaml_and_validate() {
+        let yaml = r#"
+clients: 1
+requests: 2
+flows:
+  - name: browse
+    weight: 3
+    steps:
+      - target: http://localhost:3000/products
+  - name: checkout
+    steps:
+      - target: POST http://localhost:3000/cart
+      - target: ftp://localhost:3000/pay
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert!(s.is_scenario());
+        assert_eq!(s.flows[0].weight, 3);
+        assert_eq!(s.flows[1].weight, 1);
+        let err = s.validate().unwrap_err().to_string();
+        assert!(err.contains("checkout step 2"), "{err}");
+
+        let mut s = s;
+        s.flows[1].steps.pop();
+        assert!(s.validate().is_ok());
+        let duplicate = Settings {
+            flows: vec![s.flows[0].clone(), s.flows[0].clone()],
+            ..s.clone()
+        };
+        assert!(duplicate.validate().is_err());
+        let mut zero_weight = s.clone();
+        zero_weight.flows[0].weight = 0;
+        assert!(zero_weight.validate().is_err());
+        let both = Settings {
+            steps: s.flows[0].steps.clone(),
+            ..s
+        };
+        assert!(both.validate().is_err());
+    }
+
+    #[test]
+    fn feeders_parse_from_yaml() {
+        let yaml = r#"
+clients: 1
+requests: 1
+target: http://localhost:3000/users/{{users.id}}
+feeders:
+  - name: users
+    file: data/users.CSV
+  - name: terms
+    file: terms.ndjson
+    strategy: unique
+    on_exhaustion: stop
+"#;
+        let s: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(s.feeders[0].strategy, FeederStrategy::Sequential);
+        assert_eq!(s.feeders[0].on_exhaustion, OnExhaustion::Recycle);
+        assert_eq!(s.feeders[0].format(), FeederFormat::Csv);
+        assert_eq!(s.feeders[1].strategy, FeederStrategy::Unique);
+        assert_eq!(s.feeders[1].format(), FeederFormat::Jsonl);
+        assert!(s.validate().is_ok());
+
+        let mut invalid = s.clone();
+        invalid.feeders[1].name = "users".to_string();
+        assert!(invalid.validate().is_err());
+        invalid.feeders[1].name = "search.terms".to_string();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_inconsistent_stages() {
+        let stage = |clients, rps| Stage {
+            duration: 10,
+            clients,
+            rps,
+        };
+        let with_stages = |stages| Settings {
+            stages,
+            ..base_settings()
+        };
+        assert!(with_stages(vec![stage(Some(5), Some(5))])
+            .validate()
+            .is_err());
+        assert!(with_stages(vec![stage(None, None)]).validate().is_err());
+        assert!(
+            with_stages(vec![stage(Some(5), None), stage(None, Some(5))])
+                .validate()
+                .is_err()
+        );
+        assert!(with_stages(vec![stage(Some(0), None)]).validate().is_err());
+        let rps_stages = with_stages(vec![stage(None, Some(100))]);
+        assert!(rps_stages.validate().is_ok());
+        assert!(rps_stages.is_rate_limited());
+        let with_duration = Settings {
+            duration: Some(10),
+            ..with_stages(vec![stage(Some(5), None)])
+        };
+        assert!(with_duration.validate().is_err());
+    }
 }
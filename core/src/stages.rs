@@ -0,0 +1,128 @@
+//! Staged load profiles: the load level (workers or arrival rate) as a function of time.
+
+use std::time::Duration;
+
+use crate::settings::Stage;
+
+/// One stage, moving linearly from `from` to `to` over `duration`.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    duration: Duration,
+    from: f64,
+    to: f64,
+}
+
+/// Piecewise-linear load profile built from the configured stages. Each stage starts at the
+/// level the previous one ended at (0 for the first stage).
+#[derive(Debug, Clone)]
+pub(crate) struct Schedule {
+    segments: Vec<Segment>,
+}
+
+impl Schedule {
+    pub(crate) fn new(stages: &[Stage]) -> Self {
+        let mut from = 0.0;
+        let segments = stages
+            .iter()
+            .map(|stage| {
+                let to = stage.target() as f64;
+                let segment = Segment {
+                    duration: Duration::from_secs(stage.duration),
+                    from,
+                    to,
+                };
+                from = to;
+                segment
+            })
+            .collect();
+        Schedule { segments }
+    }
+
+    /// Index of the stage running at `elapsed` and the load level at that moment, or `None`
+    /// once the profile is over.
+    pub(crate) fn at(&self, elapsed: Duration) -> Option<(usize, f64)> {
+        let mut start = Duration::ZERO;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let end = start + segment.duration;
+            if elapsed < end {
+                let progress = (elapsed - start).as_secs_f64() / segment.duration.as_secs_f64();
+                return Some((i, segment.from + (segment.to - segment.from) * progress));
+            }
+            start = end;
+        }
+        None
+    }
+
+    /// Number of arrivals due by `elapsed` when the level is a rate per second: the integral
+    /// of the profile from the start.
+    pub(crate) fn arrivals_until(&self, elapsed: Duration) -> f64 {
+        let mut start = Duration::ZERO;
+        let mut total = 0.0;
+        for segment in &self.segments {
+            let secs = segment.duration.as_secs_f64();
+            if elapsed >= start + segment.duration {
+                total += (segment.from + segment.to) / 2.0 * secs;
+            } else {
+                let t = (elapsed - start).as_secs_f64();
+                total += segment.from * t + (segment.to - segment.from) * t * t / (2.0 * secs);
+                break;
+            }
+            start += segment.duration;
+        }
+        total
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clients(duration: u64, clients: u32) -> Stage {
+        Stage {
+            duration,
+            clients: Some(clients),
+            rps: None,
+        }
+    }
+
+    fn secs(s: f64) -> Duration {
+        Duration::from_secs_f64(s)
+    }
+
+    #[test]
+    fn interpolates_between_stages() {
+        // Ramp up to 10, hold, ramp down to 0.
+        let schedule = Schedule::new(&[clients(10, 10), clients(20, 10), clients(10, 0)]);
+        assert_eq!(schedule.at(secs(0.0)), Some((0, 0.0)));
+        assert_eq!(schedule.at(secs(5.0)), Some((0, 5.0)));
+        assert_eq!(schedule.at(secs(15.0)), Some((1, 10.0)));
+        assert_eq!(schedule.at(secs(35.0)), Some((2, 5.0)));
+        assert_eq!(schedule.at(secs(40.0)), None);
+    }
+
+    #[test]
+    fn zero_length_stage_jumps_to_its_level() {
+        let schedule = Schedule::new(&[clients(0, 8), clients(10, 8)]);
+        assert_eq!(schedule.at(secs(0.0)), Some((1, 8.0)));
+        assert_eq!(schedule.at(secs(9.0)), Some((1, 8.0)));
+    }
+
+    #[test]
+    fn arrivals_integrate_the_rate() {
+        let rps = |duration, rps| Stage {
+            duration,
+            clients: None,
+            rps: Some(rps),
+        };
+        // 0 → 100 rps over 10 s, then 100 rps for 10 s.
+        let schedule = Schedule::new(&[rps(10, 100), rps(10, 100)]);
+        assert!((schedule.arrivals_until(secs(10.0)) - 500.0).abs() < 1e-6);
+        assert!((schedule.arrivals_until(secs(5.0)) - 125.0).abs() < 1e-6);
+        assert!((schedule.arrivals_until(secs(15.0)) - 1_000.0).abs() < 1e-6);
+        assert!((schedule.arrivals_until(secs(30.0)) - 1_500.0).abs() < 1e-6);
+    }
+}
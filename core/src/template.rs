@@ -0,0 +1,650 @@
+//! Request templates: `{{...}}` placeholders in URLs, headers and bodies, parsed once per step
+//! and rendered for every request.
+//!
+//! A placeholder holds one expression: a variable (`seq`, `client`, `timestamp`, `uuid`, an
+//! extracted value or a `feeder.column`), a quoted string or number literal, or a function
+//! call whose arguments are expressions themselves, e.g.
+//! `{{base64(hmac_sha256(env('API_SECRET'), seq))}}`. Calls of deterministic functions whose
+//! arguments are all literals are evaluated once, when the template is parsed.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, Result};
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::distr::Alphanumeric;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::execution::now_ms;
+
+/// Characters left as is by `urlencode`: the RFC 3986 unreserved set.
+const URLENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Format of `date` when none is given.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// A parsed template.
+#[derive(Debug, Clone)]
+pub(crate) struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    /// A placeholder and its source text, rendered as is when the expression has no value
+    /// (e.g. a variable that was never extracted).
+    Expr {
+        expr: Expr,
+        source: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(String),
+    Var(String),
+    Call { func: Func, args: Vec<Expr> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    RandomInt,
+    RandomString,
+    RandomChoice,
+    RandomFloat,
+    Date,
+    Env,
+    Base64,
+    Urlencode,
+    Sha256,
+    HmacSha256,
+}
+
+impl Func {
+    const ALL: [(&'static str, Func); 10] = [
+        ("random_int", Func::RandomInt),
+        ("random_string", Func::RandomString),
+        ("random_choice", Func::RandomChoice),
+        ("random_float", Func::RandomFloat),
+        ("date", Func::Date),
+        ("env", Func::Env),
+        ("base64", Func::Base64),
+        ("urlencode", Func::Urlencode),
+        ("sha256", Func::Sha256),
+        ("hmac_sha256", Func::HmacSha256),
+    ];
+
+    fn from_name(name: &str) -> Option<Func> {
+        Func::ALL.iter().find(|(n, _)| *n == name).map(|(_, f)| *f)
+    }
+
+    fn name(self) -> &'static str {
+        Func::ALL
+            .iter()
+            .find(|(_, f)| *f == self)
+            .map_or("", |(n, _)| n)
+    }
+
+    /// Minimum and maximum number of arguments.
+    fn arity(self) -> (usize, usize) {
+        match self {
+            Func::RandomInt | Func::HmacSha256 => (2, 2),
+            Func::RandomString | Func::Env | Func::Base64 | Func::Urlencode | Func::Sha256 => {
+                (1, 1)
+            }
+            Func::RandomChoice => (1, usize::MAX),
+            Func::RandomFloat => (0, 3),
+            Func::Date => (0, 2),
+        }
+    }
+
+    /// Whether the result only depends on the arguments, so literal calls can be evaluated
+    /// ahead of time.
+    fn is_deterministic(self) -> bool {
+        matches!(
+            self,
+            Func::Env | Func::Base64 | Func::Urlencode | Func::Sha256 | Func::HmacSha256
+        )
+    }
+}
+
+/// Per-request values templates are rendered with.
+pub(crate) struct Context<'a> {
+    pub seq: u32,
+    pub client: usize,
+    pub vars: &'a HashMap<String, String>,
+    pub rng: &'a mut SmallRng,
+}
+
+impl Template {
+    pub(crate) fn parse(input: &str) -> Result<Self> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut rest = input;
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            // `{{` not followed by an expression (e.g. `{{#each}}` in a payload) is plain text.
+            if !after.trim_start().starts_with(starts_expr) {
+                text.push_str(&rest[..start + 1]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+            text.push_str(&rest[..start]);
+            let mut parser = Parser { src: after, pos: 0 };
+            let expr = parser
+                .placeholder()
+                .with_context(|| format!("Invalid template '{}'", input))?;
+            let end = start + 2 + parser.pos;
+            match expr {
+                Expr::Literal(value) => text.push_str(&value),
+                expr => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Expr {
+                        expr,
+                        source: rest[start..end].to_string(),
+                    });
+                }
+            }
+            rest = &rest[end..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// The text of a template without placeholders.
+    pub(crate) fn as_static(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [] => Some(""),
+            [Part::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn render(&self, ctx: &mut Context) -> String {
+        if let Some(text) = self.as_static() {
+            return text.to_string();
+        }
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Expr { expr, source } => match expr.eval(ctx) {
+                    Some(value) => out.push_str(&value),
+                    None => out.push_str(source),
+                },
+            }
+        }
+        out
+    }
+}
+
+fn starts_expr(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '\'' | '"' | '-')
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Recursive-descent parser over the text following `{{`.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// An expression followed by the closing `}}`.
+    fn placeholder(&mut self) -> Result<Expr> {
+        let expr = self.expr()?;
+        self.skip_whitespace();
+        if !self.eat("}}") {
+            anyhow::bail!("expected '}}}}' at '{}'", self.rest_preview());
+        }
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(quote @ ('\'' | '"')) => self.string(quote),
+            Some(c) if c.is_ascii_digit() || c == '-' => Ok(self.number()),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => self.ident_or_call(),
+            _ => anyhow::bail!("expected an expression at '{}'", self.rest_preview()),
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<Expr> {
+        self.pos += 1;
+        let mut value = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(Expr::Literal(value));
+                }
+                c => value.push(c),
+            }
+        }
+        anyhow::bail!("unterminated string")
+    }
+
+    fn number(&mut self) -> Expr {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        self.take_while(|c| c.is_ascii_digit() || c == '.');
+        Expr::Literal(self.src[start..self.pos].to_string())
+    }
+
+    fn ident_or_call(&mut self) -> Result<Expr> {
+        let name = self.take_while(is_ident).to_string();
+        self.skip_whitespace();
+        if !self.eat("(") {
+            return Ok(Expr::Var(name));
+        }
+        let func =
+            Func::from_name(&name).with_context(|| format!("unknown function '{}'", name))?;
+        let mut args = vec![];
+        self.skip_whitespace();
+        if !self.eat(")") {
+            loop {
+                args.push(self.expr()?);
+                self.skip_whitespace();
+                if self.eat(")") {
+                    break;
+                }
+                if !self.eat(",") {
+                    anyhow::bail!("expected ',' or ')' at '{}'", self.rest_preview());
+                }
+            }
+        }
+        call(func, args)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.src[self.pos..].starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let len = self.src[start..]
+            .find(|c: char| !f(c))
+            .unwrap_or(self.src.len() - start);
+        self.pos += len;
+        &self.src[start..self.pos]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn rest_preview(&self) -> String {
+        self.src[self.pos..].chars().take(20).collect()
+    }
+}
+
+/// Checks a call's arguments and evaluates it right away when it is deterministic and only
+/// has literal arguments.
+fn call(func: Func, args: Vec<Expr>) -> Result<Expr> {
+    let (min, max) = func.arity();
+    if args.len() < min || args.len() > max {
+        anyhow::bail!(
+            "{}() takes {} arguments, got {}",
+            func.name(),
+            match (min, max) {
+                (min, max) if min == max => min.to_string(),
+                (min, usize::MAX) => format!("at least {}", min),
+                (min, max) => format!("{} to {}", min, max),
+            },
+            args.len()
+        );
+    }
+    let literals: Option<Vec<&str>> = args
+        .iter()
+        .map(|a| match a {
+            Expr::Literal(value) => Some(value.as_str()),
+            _ => None,
+        })
+        .collect();
+    if let Some(literals) = literals {
+        // Literal arguments are checked now rather than failing on every request.
+        if func.is_deterministic() {
+            return apply_deterministic(func, &literals).map(Expr::Literal);
+        }
+        check_literal_args(func, &literals)?;
+    }
+    Ok(Expr::Call { func, args })
+}
+
+fn check_literal_args(func: Func, args: &[&str]) -> Result<()> {
+    match func {
+        Func::RandomInt => {
+            for arg in args {
+                arg.parse::<i64>()
+                    .with_context(|| format!("random_int(): '{}' is not an integer", arg))?;
+            }
+        }
+        Func::RandomString => {
+            args[0]
+                .parse::<usize>()
+                .with_context(|| format!("random_string(): '{}' is not a length", args[0]))?;
+        }
+        Func::RandomFloat if random_float_args(args).is_none() => {
+            anyhow::bail!("random_float(): expected numbers, got {:?}", args);
+        }
+        Func::Date => {
+            let format = args.first().copied().unwrap_or(DEFAULT_DATE_FORMAT);
+            if format_date(format, args.get(1).copied()).is_none() {
+                anyhow::bail!("date(): invalid format or offset in {:?}", args);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn apply_deterministic(func: Func, args: &[&str]) -> Result<String> {
+    Ok(match func {
+        Func::Env => std::env::var(args[0])
+            .with_context(|| format!("env(): variable '{}' is not set", args[0]))?,
+        Func::Base64 => base64::engine::general_purpose::STANDARD.encode(args[0]),
+        Func::Urlencode => utf8_percent_encode(args[0], URLENCODE).to_string(),
+        Func::Sha256 => hex(&Sha256::digest(args[0])),
+        Func::HmacSha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(args[0].as_bytes())
+                .context("hmac_sha256(): invalid key")?;
+            mac.update(args[1].as_bytes());
+            hex(&mac.finalize().into_bytes())
+        }
+        _ => unreachable!("{} is not deterministic", func.name()),
+    })
+}
+
+impl Expr {
+    fn eval(&self, ctx: &mut Context) -> Option<String> {
+        match self {
+            Expr::Literal(value) => Some(value.clone()),
+            Expr::Var(name) => match name.as_str() {
+                "seq" => Some(ctx.seq.to_string()),
+                "client" => Some(ctx.client.to_string()),
+                "timestamp" => Some(now_ms().to_string()),
+                "uuid" => Some(uuid_v4(ctx.rng)),
+                _ => ctx.vars.get(name).cloned(),
+            },
+            Expr::Call { func, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(ctx))
+                    .collect::<Option<Vec<_>>>()?;
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                apply(*func, &args, ctx.rng)
+            }
+        }
+    }
+}
+
+fn apply(func: Func, args: &[&str], rng: &mut SmallRng) -> Option<String> {
+    match func {
+        Func::RandomInt => {
+            let a = args[0].trim().parse::<i64>().ok()?;
+            let b = args[1].trim().parse::<i64>().ok()?;
+            Some(rng.random_range(a.min(b)..=a.max(b)).to_string())
+        }
+        Func::RandomString => {
+            let len = args[0].trim().parse::<usize>().ok()?;
+            Some((0..len).map(|_| rng.sample(Alphanumeric) as char).collect())
+        }
+        Func::RandomChoice => Some(args[rng.random_range(0..args.len())].to_string()),
+        Func::RandomFloat => {
+            let (min, max, decimals) = random_float_args(args)?;
+            let value = if min < max {
+                rng.random_range(min..max)
+            } else {
+                min
+            };
+            Some(match decimals {
+                Some(d) => format!("{:.*}", d, value),
+                None => value.to_string(),
+            })
+        }
+        Func::Date => format_date(
+            args.first().copied().unwrap_or(DEFAULT_DATE_FORMAT),
+            args.get(1).copied(),
+        ),
+        _ => apply_deterministic(func, args).ok(),
+    }
+}
+
+/// `random_float([min, max[, decimals]])`, defaulting to `[0, 1)` at full precision.
+fn random_float_args(args: &[&str]) -> Option<(f64, f64, Option<usize>)> {
+    match args {
+        [] => Some((0.0, 1.0, None)),
+        [min, max] | [min, max, _] => {
+            let decimals = match args.get(2) {
+                Some(d) => Some(d.trim().parse().ok()?),
+                None => None,
+            };
+            Some((min.trim().parse().ok()?, max.trim().parse().ok()?, decimals))
+        }
+        _ => None,
+    }
+}
+
+/// The current UTC time shifted by `offset` (e.g. `-1d`, `+2h`, `30m`, `15s`, `1w`) and
+/// formatted with strftime specifiers.
+fn format_date(format: &str, offset: Option<&str>) -> Option<String> {
+    let mut date = chrono::Utc::now();
+    if let Some(offset) = offset.map(str::trim).filter(|o| !o.is_empty()) {
+        let unit_at = offset.find(|c: char| c.is_ascii_alphabetic())?;
+        let amount: i64 = offset[..unit_at].trim_start_matches('+').parse().ok()?;
+        let delta = match &offset[unit_at..] {
+            "s" => chrono::TimeDelta::try_seconds(amount)?,
+            "m" => chrono::TimeDelta::try_minutes(amount)?,
+            "h" => chrono::TimeDelta::try_hours(amount)?,
+            "d" => chrono::TimeDelta::try_days(amount)?,
+            "w" => chrono::TimeDelta::try_weeks(amount)?,
+            _ => return None,
+        };
+        date = date.checked_add_signed(delta)?;
+    }
+    let mut out = String::new();
+    // Invalid specifiers make formatting fail instead of panicking.
+    write!(out, "{}", date.format(format)).ok()?;
+    Some(out)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+            let _ = write!(s, "{:02x}", b);
+            s
+        })
+}
+
+/// A random (version 4) UUID as defined by RFC 4122, drawn from the worker's generator.
+fn uuid_v4(rng: &mut SmallRng) -> String {
+    let mut bytes: [u8; 16] = rng.random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // variant 10xx
+    let hex = hex(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn render(template: &str, vars: &[(&str, &str)]) -> String {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut ctx = Context {
+            seq: 42,
+            client: 3,
+            vars: &vars,
+            rng: &mut rng,
+        };
+        Template::parse(template).unwrap().render(&mut ctx)
+    }
+
+    #[test]
+    fn renders_variables_and_keeps_unknown_placeholders() {
+        assert_eq!(
+            render("/u/{{ seq }}/{{client}}/{{users.id}}", &[("users.id", "7")]),
+            "/u/42/3/7"
+        );
+        assert_eq!(render("{{token}}", &[]), "{{token}}");
+        // Braces that do not open an expression are plain text.
+        assert_eq!(render("{{#each}}{{{seq}}}", &[]), "{{#each}}{42}");
+    }
+
+    #[test]
+    fn uuid_is_a_random_rfc_4122_v4() {
+        let re = regex::Regex::new(
+            "^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$",
+        )
+        .unwrap();
+        let uuids = render("{{uuid}} {{uuid}}", &[]);
+        let (a, b) = uuids.split_once(' ').unwrap();
+        assert!(re.is_match(a), "{a}");
+        assert!(re.is_match(b), "{b}");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn same_seed_renders_the_same_values() {
+        let template =
+            Template::parse("{{uuid}}/{{random_int(1, 1000000)}}/{{random_string(6)}}").unwrap();
+        let vars = HashMap::new();
+        let render_with = |seed| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut ctx = Context {
+                seq: 0,
+                client: 0,
+                vars: &vars,
+                rng: &mut rng,
+            };
+            (template.render(&mut ctx), template.render(&mut ctx))
+        };
+        assert_eq!(render_with(1), render_with(1));
+        assert_ne!(render_with(1), render_with(2));
+    }
+
+    #[test]
+    fn random_functions_stay_in_range() {
+        for _ in 0..20 {
+            let n: i64 = render("{{random_int(5, -5)}}", &[]).parse().unwrap();
+            assert!((-5..=5).contains(&n));
+            let f: f64 = render("{{random_float(1, 2, 2)}}", &[]).parse().unwrap();
+            assert!((1.0..=2.0).contains(&f));
+        }
+        let s = render("{{random_string(12)}}", &[]);
+        assert_eq!(s.len(), 12);
+        assert!(s.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert!(
+            ["red", "blue"].contains(&render("{{random_choice('red', \"blue\")}}", &[]).as_str())
+        );
+        assert_eq!(
+            render("{{random_string(n)}}", &[("n", "4")]).len(),
+            4,
+            "arguments may be variables"
+        );
+    }
+
+    #[test]
+    fn encoding_and_hash_functions_compose() {
+        assert_eq!(render("{{base64('user:pass')}}", &[]), "dXNlcjpwYXNz");
+        assert_eq!(render("{{urlencode('a b&c/d')}}", &[]), "a%20b%26c%2Fd");
+        assert_eq!(
+            render("{{sha256('abc')}}", &[]),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // RFC 4231 test case 2.
+        assert_eq!(
+            render(
+                "{{hmac_sha256('Jefe', 'what do ya want for nothing?')}}",
+                &[]
+            ),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            render("{{base64(sha256(name))}}", &[("name", "abc")]),
+            "YmE3ODE2YmY4ZjAxY2ZlYTQxNDE0MGRlNWRhZTIyMjNiMDAzNjFhMzk2MTc3YTljYjQxMGZmNjFmMjAwMTVhZA=="
+        );
+    }
+
+    #[test]
+    fn date_applies_format_and_offset() {
+        let today = chrono::Utc::now().date_naive();
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(render("{{date('%Y-%m-%d')}}", &[]), today.to_string());
+        assert_eq!(
+            render("{{date('%Y-%m-%d', '+1d')}}", &[]),
+            tomorrow.to_string()
+        );
+        assert_eq!(render("{{date()}}", &[]).len(), 20);
+    }
+
+    #[test]
+    fn literal_calls_are_folded_and_checked_at_parse_time() {
+        std::env::set_var("GOKU_TEMPLATE_TEST", "secret");
+        let template = Template::parse("key={{ base64(env('GOKU_TEMPLATE_TEST')) }}").unwrap();
+        assert_eq!(template.as_static(), Some("key=c2VjcmV0"));
+
+        for invalid in [
+            "{{nope(1)}}",
+            "{{random_int(1)}}",
+            "{{random_int(1, 'x')}}",
+            "{{date('%Y', '+1y')}}",
+            "{{env('GOKU_TEMPLATE_TEST_UNSET')}}",
+            "{{random_choice()}}",
+            "{{seq",
+            "{{'open}}",
+        ] {
+            assert!(Template::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+}
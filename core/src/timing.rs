@@ -0,0 +1,384 @@
+//! Per-request connection phase timing.
+//!
+//! reqwest does not expose connection internals, so the phases are captured through the hooks it
+//! does offer: a DNS resolver, a connector layer and the rustls session store. Each hook writes
+//! into a task-local [`ConnectTimes`] that is scoped around a single `send()` by [`capture`].
+//! The same hooks tell which phase a failed connection attempt stopped in.
+//! Hooks running outside that scope (e.g. a connection finished in the background after the
+//! request picked up a pooled one) are ignored.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, Resumption, Tls12ClientSessionValue,
+    Tls13ClientSessionValue,
+};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, NamedGroup, SignatureScheme};
+use tokio::time::Instant;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::benchmark::{ErrorKind, Phases};
+
+// ---------------------------------------------------------------------------
+// Recorder
+// ---------------------------------------------------------------------------
+
+tokio::task_local! {
+    static CONNECT_TIMES: RefCell<ConnectTimes>;
+}
+
+/// Raw timestamps collected while a request opens a new connection.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ConnectTimes {
+    dns: Option<Duration>,
+    connect_start: Option<Instant>,
+    tls_start: Option<Instant>,
+    connect_end: Option<Instant>,
+    /// The host name lookup failed.
+    dns_failed: bool,
+    /// Establishing the connection (DNS, TCP and TLS) failed.
+    connect_failed: bool,
+}
+
+fn record(f: impl FnOnce(&mut ConnectTimes)) {
+    let _ = CONNECT_TIMES.try_with(|t| f(&mut t.borrow_mut()));
+}
+
+/// Runs `send` while recording the connection phases it goes through.
+pub(crate) async fn capture<F, T>(send: F) -> (T, ConnectTimes)
+where
+    F: Future<Output = T>,
+{
+    CONNECT_TIMES
+        .scope(RefCell::new(ConnectTimes::default()), async move {
+            let out = send.await;
+            let times = CONNECT_TIMES.with(|t| t.borrow().clone());
+            (out, times)
+        })
+        .await
+}
+
+impl ConnectTimes {
+    /// The phase a failed connection attempt stopped in, if one failed.
+    pub(crate) fn failed_phase(&self) -> Option<ErrorKind> {
+        if self.dns_failed {
+            Some(ErrorKind::Dns)
+        } else if !self.connect_failed {
+            None
+        } else if self.tls_start.is_some() {
+            Some(ErrorKind::Tls)
+        } else {
+            Some(ErrorKind::Connect)
+        }
+    }
+
+    /// Splits `elapsed` (the time until the response headers arrived) into DNS, TCP connect,
+    /// TLS handshake and time to first byte. Phases a reused connection skipped are `None`.
+    pub(crate) fn into_phases(self, elapsed: Duration) -> Phases {
+        let dns = self.dns.unwrap_or_default();
+        let (connect, tls) = match (self.connect_start, self.connect_end) {
+            (Some(start), Some(end)) => {
+                let tcp_end = self.tls_start.unwrap_or(end);
+                let connect = tcp_end.saturating_duration_since(start).saturating_sub(dns);
+                let tls = self.tls_start.map(|t| end.saturating_duration_since(t));
+                (Some(connect), tls)
+            }
+            _ => (None, None),
+        };
+        let setup = dns + connect.unwrap_or_default() + tls.unwrap_or_default();
+        Phases {
+            dns_us: self.dns.map(as_us),
+            connect_us: connect.map(as_us),
+            tls_us: tls.map(as_us),
+            ttfb_us: Some(as_us(elapsed.saturating_sub(setup))),
+            body_us: None,
+        }
+    }
+}
+
+fn as_us(d: Duration) -> u64 {
+    d.as_micros() as u64
+}
+
+// ---------------------------------------------------------------------------
+// DNS
+// ---------------------------------------------------------------------------
+
+/// System resolver (same `getaddrinfo` lookup reqwest uses by default) that records its duration.
+#[derive(Debug, Default)]
+pub(crate) struct TimedResolver;
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs: Vec<_> = match tokio::net::lookup_host((name.as_str(), 0)).await {
+                Ok(addrs) => addrs.collect(),
+                Err(e) => {
+                    record(|t| t.dns_failed = true);
+                    return Err(e.into());
+                }
+            };
+            record(|t| t.dns = Some(start.elapsed()));
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Connector layer
+// ---------------------------------------------------------------------------
+
+/// Connector layer timing the whole connection establishment (DNS + TCP + TLS).
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ConnectTimingLayer;
+
+impl<S> Layer<S> for ConnectTimingLayer {
+    type Service = ConnectTiming<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectTiming { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectTiming<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for ConnectTiming<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let connecting = self.inner.call(req);
+        Box::pin(async move {
+            let start = Instant::now();
+            let conn = connecting.await;
+            record(|t| {
+                t.connect_start = Some(start);
+                t.connect_end = Some(Instant::now());
+                t.connect_failed = conn.is_err();
+            });
+            conn
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TLS
+// ---------------------------------------------------------------------------
+
+/// Builds the rustls configuration reqwest would use by default, with a session store that
+/// marks the start of each TLS handshake (rustls consults it while building the ClientHello).
+pub(crate) fn tls_config(http2: bool, insecure: bool) -> Result<ClientConfig> {
+    let provider = CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
+
+    let builder = ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .context("Invalid TLS protocol versions")?
+        .dangerous();
+    let builder = if insecure {
+        builder.with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+    } else {
+        let verifier = rustls_platform_verifier::Verifier::new(provider)
+            .context("Cannot load platform TLS certificates")?;
+        builder.with_custom_certificate_verifier(Arc::new(verifier))
+    };
+
+    let mut tls = builder.with_no_client_auth();
+    tls.alpn_protocols = if http2 {
+        vec![b"h2".to_vec()]
+    } else {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    };
+    tls.resumption = Resumption::store(Arc::new(HandshakeStartStore(
+        ClientSessionMemoryCache::new(256),
+    )));
+    Ok(tls)
+}
+
+/// Session cache wrapper: the first lookup on a new connection is the start of its handshake.
+#[derive(Debug)]
+struct HandshakeStartStore(ClientSessionMemoryCache);
+
+fn mark_tls_start() {
+    record(|t| {
+        t.tls_start.get_or_insert_with(Instant::now);
+    });
+}
+
+impl ClientSessionStore for HandshakeStartStore {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.0.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        mark_tls_start();
+        self.0.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.0.set_tls12_session(server_name, value)
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        mark_tls_start();
+        self.0.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.0.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        self.0.insert_tls13_ticket(server_name, value)
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        mark_tls_start();
+        self.0.take_tls13_ticket(server_name)
+    }
+}
+
+/// Certificate verifier for `--insecure`: accepts any certificate, like reqwest's
+/// `danger_accept_invalid_certs`.
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_connection_is_all_ttfb() {
+        let phases = ConnectTimes::default().into_phases(Duration::from_micros(900));
+        assert_eq!(phases.dns_us, None);
+        assert_eq!(phases.connect_us, None);
+        assert_eq!(phases.tls_us, None);
+        assert_eq!(phases.ttfb_us, Some(900));
+    }
+
+    #[test]
+    fn new_tls_connection_is_split_into_phases() {
+        let start = Instant::now();
+        let times = ConnectTimes {
+            dns: Some(Duration::from_micros(100)),
+            connect_start: Some(start),
+            tls_start: Some(start + Duration::from_micros(300)),
+            connect_end: Some(start + Duration::from_micros(1_000)),
+            ..ConnectTimes::default()
+        };
+        let phases = times.into_phases(Duration::from_micros(1_500));
+        assert_eq!(phases.dns_us, Some(100));
+        assert_eq!(phases.connect_us, Some(200));
+        assert_eq!(phases.tls_us, Some(700));
+        assert_eq!(phases.ttfb_us, Some(500));
+    }
+
+    #[test]
+    fn plain_http_connection_has_no_tls_phase() {
+        let start = Instant::now();
+        let times = ConnectTimes {
+            dns: None,
+            connect_start: Some(start),
+            tls_start: None,
+            connect_end: Some(start + Duration::from_micros(250)),
+            ..ConnectTimes::default()
+        };
+        let phases = times.into_phases(Duration::from_micros(1_000));
+        assert_eq!(phases.connect_us, Some(250));
+        assert_eq!(phases.tls_us, None);
+        assert_eq!(phases.ttfb_us, Some(750));
+    }
+
+    #[test]
+    fn failed_phase_follows_the_connection_attempt() {
+        assert_eq!(ConnectTimes::default().failed_phase(), None);
+        let dns = ConnectTimes {
+            dns_failed: true,
+            connect_failed: true,
+            ..ConnectTimes::default()
+        };
+        assert_eq!(dns.failed_phase(), Some(ErrorKind::Dns));
+        let tcp = ConnectTimes {
+            connect_failed: true,
+            ..ConnectTimes::default()
+        };
+        assert_eq!(tcp.failed_phase(), Some(ErrorKind::Connect));
+        let tls = ConnectTimes {
+            tls_start: Some(Instant::now()),
+            connect_failed: true,
+            ..ConnectTimes::default()
+        };
+        assert_eq!(tls.failed_phase(), Some(ErrorKind::Tls));
+    }
+}
@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use goku_core::benchmark::{Metrics, Report};
 use goku_core::execution::run;
-use goku_core::settings::{Header, Settings};
+use goku_core::settings::{Executor, Header, Payload, Settings};
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::{
     handler::server::tool::ToolRouter, model::*, prompt_router, tool, tool_handler, tool_router,
@@ -83,6 +83,32 @@ pub struct BenchmarkRequest {
         description = "Maximum total requests per second across all clients. Omit or set to 0 for unlimited."
     )]
     pub rps: Option<u32>,
+
+    /// Load model: "closed" (default) or "constant-arrival-rate".
+    #[schemars(
+        description = "Load model. 'closed' (default) waits for each response before sending the next request; \
+        'constant-arrival-rate' starts requests at `rps` regardless of response times, with `clients` as the maximum in flight."
+    )]
+    pub executor: Option<String>,
+
+    /// Read response bodies to the end.
+    #[schemars(
+        description = "Set to true to read every response body to the end. Latency then includes the body download \
+        and bytes received / throughput are reported."
+    )]
+    pub read_body: Option<bool>,
+
+    /// Keep a cookie jar per worker.
+    #[schemars(
+        description = "Set to true to give each worker its own cookie jar, so cookies set by the target are sent back on later requests."
+    )]
+    pub cookies: Option<bool>,
+
+    /// Seed for generated template values.
+    #[schemars(
+        description = "Seed for random template values ({{uuid}}, {{random_int(...)}}, …). The same seed generates the same values on every run."
+    )]
+    pub seed: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -91,6 +117,7 @@ pub struct BenchmarkRequest {
 
 #[derive(Clone)]
 pub struct GokuMcpServer {
+    #[allow(dead_code)]
     tool_router: ToolRouter<Self>,
 }
 
@@ -110,8 +137,9 @@ impl GokuMcpServer {
     /// Runs a full HTTP benchmark and returns a JSON report with all metrics.
     ///
     /// Returns a JSON object containing: concurrency, duration_secs, total_requests,
-    /// requests_per_sec, mean_ms, min_ms, max_ms, p50_ms, p95_ms, p99_ms, p999_ms,
-    /// status_2xx, status_4xx, status_5xx, status_other, network_errors.
+    /// requests_per_sec, mean/min/max and p50/p95/p99/p999 latencies (in `_ms` and `_us`),
+    /// status_2xx, status_4xx, status_5xx, status_other, network_errors, dropped_iterations,
+    /// bytes received/sent with MB/s throughput, per-phase timings and, when `rps` is set, the coordinated-omission corrected percentiles.
     #[tool(
         description = "Run an HTTP load test and return a full benchmark report as JSON. \
         Supports configuring concurrency, total requests or duration, request body, headers, \
@@ -138,21 +166,22 @@ impl GokuMcpServer {
 impl GokuMcpServer {
     async fn execute_benchmark(req: BenchmarkRequest) -> anyhow::Result<Report> {
         // Parse headers from "Name:Value" strings
-        let headers = req
-            .headers
-            .as_deref()
-            .map(|hs| {
-                hs.iter()
-                    .map(|h| {
-                        let mut parts = h.splitn(2, ':');
-                        let key = parts.next().unwrap_or("").trim().to_string();
-                        let value = parts.next().unwrap_or("").trim().to_string();
-                        Header { key, value }
-                    })
-                    .collect::<Vec<_>>()
-            });
+        let headers = req.headers.as_deref().map(|hs| {
+            hs.iter()
+                .map(|h| {
+                    let mut parts = h.splitn(2, ':');
+                    let key = parts.next().unwrap_or("").trim().to_string();
+                    let value = parts.next().unwrap_or("").trim().to_string();
+                    Header { key, value }
+                })
+                .collect::<Vec<_>>()
+        });
 
         let requests = req.requests.unwrap_or(1);
+        let executor = match req.executor.as_deref() {
+            None => Executor::Closed,
+            Some(e) => e.parse()?,
+        };
         let timeout = Duration::from_millis(req.timeout_ms.unwrap_or(30_000));
 
         let settings = Settings {
@@ -160,7 +189,10 @@ impl GokuMcpServer {
             requests,
             target: req.target,
             keep_alive: None,
-            body: req.body,
+            body: req.body.map(Payload::Text),
+            body_file: None,
+            form: None,
+            multipart: None,
             headers,
             duration: req.duration_secs,
             verbose: false,
@@ -177,6 +209,18 @@ impl GokuMcpServer {
             live_stats: None,
             pool_idle_timeout: None,
             disable_keepalive: false,
+            executor,
+            read_body: req.read_body.unwrap_or(false),
+            checks: vec![],
+            cookies: req.cookies.unwrap_or(false),
+            reset_session: false,
+            stages: vec![],
+            think_time: None,
+            flows: vec![],
+            setup: vec![],
+            teardown: vec![],
+            feeders: vec![],
+            seed: req.seed,
         };
 
         settings
@@ -186,6 +230,9 @@ impl GokuMcpServer {
         let channel_capacity = (settings.clients as usize * 2).min(4096);
         let (benchmark_tx, mut benchmark_rx) = mpsc::channel(channel_capacity);
         let mut report = Report::new(settings.clients);
+        if settings.is_rate_limited() {
+            report = report.with_corrected_histogram();
+        }
 
         run(settings, benchmark_tx, None)
             .await
@@ -206,26 +253,74 @@ impl GokuMcpServer {
 fn build_json_report(r: &Report) -> String {
     let elapsed = r.start.elapsed().as_secs_f64();
     let bd = r.status_breakdown();
-    let min = r.results.min();
-    let max = r.results.max();
+    let min = r.min();
+    let max = r.max();
+    let round2 = |v: f64| format!("{:.2}", v).parse::<f64>().unwrap_or(0.0);
+    let corrected = |q: f64| r.corrected_hist.as_ref().map(|h| h.value_at_quantile(q));
+    let phases: serde_json::Map<String, serde_json::Value> = r
+        .recorded_phases()
+        .map(|(name, h)| {
+            let stats = serde_json::json!({
+                "count": h.len(),
+                "mean_us": round2(h.mean()),
+                "p50_us": h.value_at_quantile(0.50),
+                "p95_us": h.value_at_quantile(0.95),
+                "p99_us": h.value_at_quantile(0.99),
+                "max_us": h.max(),
+            });
+            (name.to_string(), stats)
+        })
+        .collect();
 
     let data = serde_json::json!({
         "concurrency": r.clients,
         "duration_secs": format!("{:.3}", elapsed).parse::<f64>().unwrap_or(0.0),
         "total_requests": r.hist.len(),
-        "requests_per_sec": format!("{:.2}", r.requests_per_second()).parse::<f64>().unwrap_or(0.0),
-        "mean_ms": format!("{:.2}", r.hist.mean()).parse::<f64>().unwrap_or(0.0),
-        "min_ms": min,
-        "max_ms": max,
-        "p50_ms": r.hist.value_at_quantile(0.50),
-        "p95_ms": r.hist.value_at_quantile(0.95),
-        "p99_ms": r.hist.value_at_quantile(0.99),
-        "p999_ms": r.hist.value_at_quantile(0.999),
+        "requests_per_sec": round2(r.requests_per_second()),
+        "mean_ms": round2(r.hist.mean() / 1_000.0),
+        "min_ms": min / 1_000,
+        "max_ms": max / 1_000,
+        "p50_ms": r.hist.value_at_quantile(0.50) / 1_000,
+        "p95_ms": r.hist.value_at_quantile(0.95) / 1_000,
+        "p99_ms": r.hist.value_at_quantile(0.99) / 1_000,
+        "p999_ms": r.hist.value_at_quantile(0.999) / 1_000,
+        "mean_us": round2(r.hist.mean()),
+        "min_us": min,
+        "max_us": max,
+        "p50_us": r.hist.value_at_quantile(0.50),
+        "p95_us": r.hist.value_at_quantile(0.95),
+        "p99_us": r.hist.value_at_quantile(0.99),
+        "p999_us": r.hist.value_at_quantile(0.999),
         "status_2xx": bd.success,
         "status_4xx": bd.client_error,
         "status_5xx": bd.server_error,
         "status_other": bd.other,
         "network_errors": bd.network_error,
+        "status_codes": r.status_codes.iter()
+            .map(|(code, count)| (code.to_string(), serde_json::json!(count)))
+            .collect::<serde_json::Map<_, _>>(),
+        "errors": r.errors.iter()
+            .map(|(kind, e)| (kind.to_string(), serde_json::json!({
+                "count": e.count,
+                "samples": e.samples,
+            })))
+            .collect::<serde_json::Map<_, _>>(),
+        "dropped_iterations": r.dropped,
+        "check_failures": r.check_failures,
+        "checks": r.checks.iter().map(|c| serde_json::json!({
+            "name": c.name,
+            "passed": c.passed,
+            "failed": c.failed,
+        })).collect::<Vec<_>>(),
+        "bytes_received": r.bytes_received,
+        "bytes_sent": r.bytes_sent,
+        "received_mb_per_sec": round2(r.received_mb_per_sec()),
+        "sent_mb_per_sec": round2(r.sent_mb_per_sec()),
+        "corrected_p50_us": corrected(0.50),
+        "corrected_p95_us": corrected(0.95),
+        "corrected_p99_us": corrected(0.99),
+        "corrected_p999_us": corrected(0.999),
+        "phases": phases,
     });
 
     serde_json::to_string_pretty(&data).unwrap_or_else(|e| format!("{{\"error\": \"{e}\"}}"))